@@ -60,4 +60,10 @@ pub struct Receipt {
     /// EIP-2718 type
     #[serde(rename = "type")]
     pub transaction_type: U256,
+    /// Storage deposit charged from the sender, in wei. Not a part of Ethereum receipts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_deposit_charged: Option<U256>,
+    /// Storage deposit refunded to the sender, in wei. Not a part of Ethereum receipts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_deposit_refunded: Option<U256>,
 }
@@ -68,10 +68,10 @@ pub struct TransactionRequest {
 impl From<TransactionRequest> for TxMessage {
     fn from(req: TransactionRequest) -> Self {
         TxMessage(LegacyTransactionMessage {
-            nonce: req.nonce.unwrap_or_default(), // This doesn't count anyways (TODO though should it?)
-            gas_price: req.gas_price.unwrap_or_default(), // This doesn't count anyways
-            gas_limit: req.gas.unwrap_or_default(), // No gas_limit defaults 0 (TODO could be changed to MAX (no limit))
-            value: req.value.unwrap_or_default(),   // No value defaults to 0
+            nonce: req.nonce.unwrap_or_default(), // No nonce gets filled in by the RPC
+            gas_price: req.gas_price.unwrap_or_default(), // No gas price gets filled in by the RPC
+            gas_limit: req.gas.unwrap_or_default(), // No gas limit gets filled in by the RPC
+            value: req.value.unwrap_or_default(), // No value defaults to 0
             input: req.data.map(|s| s.into_vec()).unwrap_or_default(), // No data defaults to vec![]
             action: match req.to {
                 Some(to) => ethereum::TransactionAction::Call(to),
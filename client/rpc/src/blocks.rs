@@ -1,5 +1,6 @@
 use super::*;
-use crate::types::SubstrateBlock;
+use crate::types::{with_eth_transactions, SubstrateBlock};
+use ep_eth::{Block as EthBlock, TransactionStatus};
 
 impl<B, C, P> EthRPC<B, C, P>
 where
@@ -25,24 +26,59 @@ where
             .map(|r| r.map(SubstrateBlock::new))
     }
 
+    /// Fetch Ethereum block emulated by the runtime for the Substrate block with given hash,
+    /// along with statuses of the transactions executed in it.
+    pub(crate) fn eth_block_by_hash(
+        &self,
+        hash: H256,
+    ) -> RpcResult<Option<(EthBlock, Vec<TransactionStatus>)>> {
+        // Runtimes before version 2 of the API emulate no Ethereum blocks
+        if api_version::<B, C>(&self.client, hash)? < 2 {
+            return Ok(None);
+        }
+        let api = self.client.runtime_api();
+        let block = api
+            .current_block(hash)
+            .map_err(|err| rpc_err!("Fetching runtime current_block failed: {:?}", err))?;
+        let statuses = api.current_transaction_statuses(hash).map_err(|err| {
+            rpc_err!(
+                "Fetching runtime current_transaction_statuses failed: {:?}",
+                err
+            )
+        })?;
+
+        Ok(block.zip(statuses))
+    }
+
+    /// Convert Substrate block with given hash to RichBlock,
+    /// filled with Ethereum transactions executed in it.
+    fn rich_block(&self, hash: H256, full: bool) -> RpcResult<Option<RichBlock>> {
+        let Some(block) = self.substrate_block_by_hash(hash)? else {
+            return Ok(None);
+        };
+        let rich = RichBlock::from(block);
+
+        Ok(Some(match self.eth_block_by_hash(hash)? {
+            Some((eth_block, statuses)) => with_eth_transactions(rich, eth_block, statuses, full),
+            None => rich,
+        }))
+    }
+
     /// Fetch Substrate block by its hash and convert it to RichBlock.
-    pub async fn block_by_hash(&self, hash: H256, _full: bool) -> RpcResult<Option<RichBlock>> {
-        Ok(self.substrate_block_by_hash(hash)?.map(RichBlock::from))
+    pub async fn block_by_hash(&self, hash: H256, full: bool) -> RpcResult<Option<RichBlock>> {
+        self.rich_block(hash, full)
     }
 
     /// Fetch Substrate block by its number and convert it to RichBlock.
     pub async fn block_by_number(
         &self,
         number: BlockNumber,
-        _full: bool,
+        full: bool,
     ) -> RpcResult<Option<RichBlock>> {
-        Ok(
-            if let Some(hash) = self.substrate_block_hash_by_number(number).await? {
-                self.substrate_block_by_hash(hash)?.map(RichBlock::from)
-            } else {
-                None
-            },
-        )
+        match self.substrate_block_hash_by_number(number).await? {
+            Some(hash) => self.rich_block(hash, full),
+            None => Ok(None),
+        }
     }
 
     /// Get number of transactions in a block fetched by its hash.
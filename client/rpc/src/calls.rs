@@ -1,6 +1,8 @@
 use crate::{types::EthereumSigner, CallRequest, *};
 use ep_eth::{AccountId20, EnvelopedDecodable, LegacyTransaction, LegacyTransactionMessage};
 use frame_support::weights::Weight;
+use jsonrpsee::types::error::ErrorObjectOwned;
+use pallet_ethink::{DryRunError, StateOverride};
 
 impl<B, C, P> EthRPC<B, C, P>
 where
@@ -29,11 +31,49 @@ where
     pub async fn send_transaction(&self, request: TransactionRequest) -> RpcResult<H256> {
         let hash = self.client.info().best_hash;
 
-        let TransactionRequest { from, .. } = request.clone();
-        let from: AccountId20 = from
-            .ok_or(rpc_err!("no origin account provided for tx"))?
-            .into();
-        let msg = TxMessage::from(request).0;
+        let TransactionRequest {
+            from,
+            to,
+            gas_price,
+            gas,
+            value,
+            data,
+            nonce,
+            ..
+        } = request.clone();
+        let from = from.ok_or(rpc_err!("no origin account provided for tx"))?;
+        let mut msg = TxMessage::from(request).0;
+        // No gas price defaults to the minimum one, which the runtime accepts
+        if gas_price.is_none() {
+            msg.gas_price = self.gas_price()?;
+        }
+        // No gas limit defaults to the estimated one
+        if gas.is_none() {
+            let call = CallRequest {
+                from: Some(from),
+                to,
+                value,
+                data,
+                ..Default::default()
+            };
+            msg.gas_limit = self.estimate_gas(call, None).await?;
+        }
+        // No nonce defaults to the next one for the sender account
+        if nonce.is_none() {
+            msg.nonce = self
+                .client
+                .runtime_api()
+                .nonce(hash, from)
+                .map_err(|err| rpc_err!("Fetching runtime account nonce failed: {:?}", err))?;
+        }
+        let from: AccountId20 = from.into();
+        // Sign with EIP-155 replay protection
+        msg.chain_id = Some(
+            self.client
+                .runtime_api()
+                .chain_id(hash)
+                .map_err(|err| rpc_err!("Fetching runtime chain_id failed: {:?}", err))?,
+        );
 
         // Lookup keystore for a proper key for signing
         let signer = EthereumSigner::try_from((self.keystore.clone(), from)).map_err(rpc_err)?;
@@ -68,10 +108,10 @@ where
     pub async fn call(
         &self,
         request: CallRequest,
-        _number: Option<BlockNumber>,
-        _state_overrides: Option<BTreeMap<H160, CallStateOverride>>,
+        number: Option<BlockNumber>,
+        state_overrides: Option<BTreeMap<H160, CallStateOverride>>,
     ) -> RpcResult<Bytes> {
-        let hash = self.client.info().best_hash;
+        let hash = block_hash::<B, C>(&self.client, number).await?;
 
         let CallRequest {
             from,
@@ -84,69 +124,155 @@ where
         // some calls like e.g. ERC20::decimals() don't have _from
         let from = from.unwrap_or_default();
         let to = to.ok_or(rpc_err!("empty `to` in call rq"))?;
-        // No value defaults to 0
-        let value = value
+        if api_version::<B, C>(&self.client, hash)? < 2 {
+            if state_overrides.as_ref().is_some_and(|o| !o.is_empty()) {
+                return Err(rpc_err!("State overrides are not supported by the runtime"));
+            }
+            // Set proof_size weight limit to MAX: runtime is configured not to charge fees for it
+            let gas_limit = Weight::from_parts(Self::legacy_gas(gas)?, u64::MAX);
+            return self
+                .client
+                .runtime_api()
+                .call_before_version_2(
+                    hash,
+                    from,
+                    to,
+                    data.unwrap_or_default().0,
+                    Self::legacy_value(value)?,
+                    gas_limit,
+                )
+                .map_err(|err| rpc_err!("execution fatal: {:?}", err))?
+                .map_err(|err| rpc_err!("runtime error on eth_call(): {:?}", err))
+                .map(From::from);
+        }
+        // No value defaults to 0, runtime converts it to native balance
+        let value = value.unwrap_or_default();
+        // No gas limit defaults to the block one, runtime maps it to weight
+        let gas_limit = match gas {
+            Some(gas) => gas,
+            None => self.block_gas_limit(hash)?,
+        };
+        // Overrides are applied for the call only, and rolled back by the runtime afterwards
+        let overrides = state_overrides
             .unwrap_or_default()
-            .try_into()
-            .map_err(|_| rpc_err!("bad `value` in call rq"))?;
-        // Set ref_time weight limit to MAX if not provided
-        let gas: u64 = gas
-            .unwrap_or(U256::from(u64::MAX))
-            .try_into()
-            .map_err(|_| rpc_err!("bad `gas` in call rq"))?;
-        // Set proof_size weight limit to MAX: ethink runtime is configured not to charge fees for it
-        let gas_limit = Weight::from_parts(gas, u64::MAX);
+            .into_iter()
+            .map(|(address, state)| (address, Self::state_override(state)))
+            .collect();
 
         self.client
             .runtime_api()
-            .call(
+            .call_with_overrides(
                 hash,
                 from.into(),
                 to.into(),
                 data.unwrap_or_default().0, // No data defaults to vec![]
                 value,
                 gas_limit,
+                overrides,
             )
             .map_err(|err| rpc_err!("execution fatal: {:?}", err))?
-            .map_err(|err| rpc_err!("runtime error on eth_call(): {:?}", err))
+            .map_err(|e| dry_run_err("eth_call", e))
             .map(From::from)
     }
 
-    // for this we do same as for call() but return consumed gas val
-    // we return ref_time() part of the Weight consumed, as U256
+    // for this we do same as for call() but return consumed gas val,
+    // which the runtime maps from the Weight consumed
     pub async fn estimate_gas(
         &self,
         request: CallRequest,
-        _number: Option<BlockNumber>,
+        number: Option<BlockNumber>,
     ) -> RpcResult<U256> {
-        let hash = self.client.info().best_hash;
+        let hash = block_hash::<B, C>(&self.client, number).await?;
 
         let CallRequest {
             from,
             to,
             value,
             data,
+            gas,
             ..
         } = request;
-        // No value defaults to 0
-        let value = value
+        let from = from.ok_or(rpc_err!("empty `from` in call rq"))?;
+        if api_version::<B, C>(&self.client, hash)? < 2 {
+            return self
+                .client
+                .runtime_api()
+                .gas_estimate_before_version_2(
+                    hash,
+                    from,
+                    to.ok_or(rpc_err!("empty `to` in call rq"))?,
+                    data.unwrap_or_default().0,
+                    Self::legacy_value(value)?,
+                    Weight::MAX,
+                )
+                .map_err(|err| rpc_err!("execution fatal: {:?}", err))?
+                .map_err(|err| rpc_err!("runtime error on eth_estimateGas(): {:?}", err));
+        }
+        // No value defaults to 0, runtime converts it to native balance
+        let value = value.unwrap_or_default();
+        // Gas limit caps the estimation, no gas limit defaults to the block one
+        let gas_limit = match gas {
+            Some(gas) => gas,
+            None => self.block_gas_limit(hash)?,
+        };
+
+        self.client
+            .runtime_api()
+            .gas_estimate(hash, from, to, data.unwrap_or_default().0, value, gas_limit)
+            .map_err(|err| rpc_err!("execution fatal: {:?}", err))?
+            .map_err(|e| dry_run_err("eth_estimateGas", e))
+    }
+
+    /// State override for the runtime out of the one requested
+    fn state_override(state: CallStateOverride) -> StateOverride {
+        let CallStateOverride {
+            balance,
+            nonce,
+            code,
+            state,
+            state_diff,
+        } = state;
+
+        StateOverride {
+            balance,
+            nonce,
+            code: code.map(|c| c.0),
+            state,
+            state_diff,
+        }
+    }
+
+    /// Value in native balance, as runtimes before version 2 of the API take it for dry runs
+    fn legacy_value(value: Option<U256>) -> RpcResult<u128> {
+        value
             .unwrap_or_default()
             .try_into()
-            .map_err(|_| rpc_err!("bad `value` in call rq"))?;
-        // For gas estimation we set limit to max to allow dry call pass
-        let gas_limit = Weight::MAX;
+            .map_err(|_| rpc_err!("bad `value` in call rq"))
+    }
+
+    /// Gas limit as weight ref_time, as runtimes before version 2 of the API take it for dry runs
+    fn legacy_gas(gas: Option<U256>) -> RpcResult<u64> {
+        gas.unwrap_or(U256::from(u64::MAX))
+            .try_into()
+            .map_err(|_| rpc_err!("bad `gas` in call rq"))
+    }
 
+    /// Maximum gas a transaction can spend, as reported by the runtime
+    fn block_gas_limit(&self, hash: B::Hash) -> RpcResult<U256> {
         self.client
             .runtime_api()
-            .gas_estimate(
-                hash,
-                from.ok_or(rpc_err!("empty `from` in call rq"))?,
-                to.ok_or(rpc_err!("empty `to` in call rq"))?,
-                data.unwrap_or_default().0,
-                value,
-                gas_limit,
-            )
-            .map_err(|err| rpc_err!("execution fatal: {:?}", err))?
-            .map_err(|err| rpc_err!("runtime error on eth_call(): {:?}", err))
+            .block_gas_limit(hash)
+            .map_err(|err| rpc_err!("Fetching runtime block_gas_limit failed: {:?}", err))
+    }
+}
+
+/// RPC error for the failed dry run of the method given.
+/// Reverts are reported as execution errors carrying the revert data, as Ethereum clients expect.
+fn dry_run_err(method: &str, e: DryRunError) -> ErrorObjectOwned {
+    match e {
+        DryRunError::Reverted(data) => err(EXECUTION_ERROR_CODE, "execution reverted", Some(&data)),
+        DryRunError::Failed(error) => {
+            rpc_err(format!("runtime error on {}(): {:?}", method, error))
+        }
     }
 }
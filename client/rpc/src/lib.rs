@@ -31,6 +31,9 @@ mod transactions;
 // for we might later switch to fc-rpc-core.
 mod types;
 
+// Off-chain mapping of Ethereum transactions and blocks to Substrate blocks
+pub mod mapping;
+
 use ep_eth::{EthTransaction, H160, H256, H64, U256, U64};
 use ethink_rpc_core::types::*;
 use futures::future::TryFutureExt;
@@ -39,7 +42,7 @@ use pallet_ethink::EthinkAPI;
 use sc_client_api::BlockBackend;
 use sc_network_sync::SyncingService;
 use sc_transaction_pool_api::TransactionPool;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_core::crypto::KeyTypeId;
 use sp_keystore::Keystore;
@@ -50,9 +53,13 @@ use sp_runtime::{
 use std::{collections::BTreeMap, sync::Arc};
 
 pub use ethink_rpc_core::{types::Transaction as Tx, EthApiServer};
+pub use mapping::{InMemoryStore, MappingDb, MappingSyncWorker, ParityDbStore};
 
 pub const ETHINK_KEYTYPE_ID: KeyTypeId = KeyTypeId(*b"ethi");
 
+/// Error code of the execution reverted, as Ethereum clients expect it
+pub const EXECUTION_ERROR_CODE: i32 = 3;
+
 pub fn err<T: ToString>(
     code: i32,
     message: T,
@@ -78,7 +85,25 @@ where
     C: HeaderBackend<B> + 'static,
 {
     Ok(match n.unwrap_or_default() {
-        BlockNumber::Hash { hash, .. } => hash,
+        BlockNumber::Hash {
+            hash,
+            require_canonical,
+        } => {
+            // EIP-1898: block should be known, and be on the canonical chain if required so
+            let num = client
+                .number(hash)
+                .map_err(|err| rpc_err!("Failed fetching block number by hash: {:?}", err))?
+                .ok_or(rpc_err!("Can't find block header on chain: {:?}", hash))?;
+            if require_canonical
+                && client
+                    .hash(num)
+                    .map_err(|err| rpc_err!("Failed fetching block hash by number: {:?}", err))?
+                    != Some(hash)
+            {
+                return Err(rpc_err!("Block is not canonical: {:?}", hash));
+            }
+            hash
+        }
         BlockNumber::Num(num) => {
             // block num in Substrate db is u32
             // https://github.com/paritytech/polkadot-sdk/blob/73c2bca9cdb17f1fdc2afd7aed826d0c55b8640a/substrate/client/rpc/src/chain/mod.rs#L75
@@ -96,6 +121,36 @@ where
     })
 }
 
+/// Version of the Ethink API the runtime provides at the block given.
+///
+/// Runtimes before version 2 emulate no Ethereum blocks, and take native balance and weight
+/// for dry runs, which they support with no state overrides.
+pub fn api_version<B, C>(client: &C, hash: B::Hash) -> RpcResult<u32>
+where
+    B: BlockT,
+    C: ProvideRuntimeApi<B>,
+    C::Api: EthinkAPI<B>,
+{
+    client
+        .runtime_api()
+        .api_version::<dyn EthinkAPI<B>>(hash)
+        .map_err(|err| rpc_err!("Fetching runtime API version failed: {:?}", err))?
+        .ok_or(rpc_err!("Runtime provides no Ethink API"))
+}
+
+/// Ensure the runtime at the block given supports the RPC method, which needs version 2 of the API.
+pub fn ensure_api_v2<B, C>(client: &C, hash: B::Hash, method: &str) -> RpcResult<()>
+where
+    B: BlockT,
+    C: ProvideRuntimeApi<B>,
+    C::Api: EthinkAPI<B>,
+{
+    if api_version::<B, C>(client, hash)? < 2 {
+        return Err(rpc_err(format!("{method} is not supported by the runtime")));
+    }
+    Ok(())
+}
+
 #[macro_export]
 macro_rules! rpc_err {
     ( $msg:literal ) => {
@@ -113,9 +168,14 @@ pub struct EthRPC<B: BlockT, C, P> {
     pool: Arc<P>,
     keystore: Arc<dyn Keystore>,
     sync: Arc<SyncingService<B>>,
+    mapping: Arc<MappingDb<B>>,
+    decode: EthTransactionDecoder<B>,
     _phantom: PhantomData<B>,
 }
 
+/// Extracts Ethereum transaction out of the extrinsic, provided it is an `Ethink::transact` call.
+pub type EthTransactionDecoder<B> = fn(&<B as BlockT>::Extrinsic) -> Option<EthTransaction>;
+
 impl<B, C, P> EthRPC<B, C, P>
 where
     B: BlockT<Hash = sp_core::H256>,
@@ -128,12 +188,16 @@ where
         pool: Arc<P>,
         keystore: Arc<dyn Keystore>,
         sync: Arc<SyncingService<B>>,
+        mapping: Arc<MappingDb<B>>,
+        decode: EthTransactionDecoder<B>,
     ) -> Self {
         Self {
             client,
             pool,
             keystore,
             sync,
+            mapping,
+            decode,
             _phantom: PhantomData,
         }
     }
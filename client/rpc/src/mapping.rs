@@ -0,0 +1,380 @@
+//! Off-chain mapping of Ethereum transactions and blocks to the Substrate blocks
+//! they were executed in, kept in sync with the canonical chain by [`MappingSyncWorker`].
+use ep_eth::{TransactionStatus, H256};
+use futures::StreamExt;
+use pallet_ethink::EthinkAPI;
+use sc_client_api::{Backend, BlockchainEvents};
+use scale_codec::{Decode, Encode};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_runtime::traits::Block as BlockT;
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+/// Key of the last Substrate block the mapping is synced to
+const BEST_KEY: &[u8] = b"best";
+/// Prefix of the keys of Ethereum transactions
+const TRANSACTION_PREFIX: &[u8] = b"tx:";
+/// Prefix of the keys of Ethereum blocks
+const ETH_BLOCK_PREFIX: &[u8] = b"eth_block:";
+/// Prefix of the keys of Substrate blocks
+const BLOCK_PREFIX: &[u8] = b"block:";
+
+/// Key-value store the mapping is kept in.
+pub trait KeyValueStore: Send + Sync {
+    /// Value stored at the key, if any.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String>;
+    /// Apply the changes all at once, `None` values remove the keys.
+    fn commit(&self, changes: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<(), String>;
+}
+
+/// Store keeping the mapping in memory, e.g. for tests.
+#[derive(Default)]
+pub struct InMemoryStore(RwLock<HashMap<Vec<u8>, Vec<u8>>>);
+
+impl KeyValueStore for InMemoryStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        let map = self.0.read().map_err(|e| e.to_string())?;
+        Ok(map.get(key).cloned())
+    }
+
+    fn commit(&self, changes: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<(), String> {
+        let mut map = self.0.write().map_err(|e| e.to_string())?;
+        for (key, value) in changes {
+            match value {
+                Some(value) => map.insert(key, value),
+                None => map.remove(&key),
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Store keeping the mapping on disk, in ParityDb.
+pub struct ParityDbStore(parity_db::Db);
+
+impl ParityDbStore {
+    /// Open the store at the path given, creating it if there is none.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        parity_db::Db::open_or_create(&parity_db::Options::with_columns(path, 1))
+            .map(Self)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl KeyValueStore for ParityDbStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        self.0.get(0, key).map_err(|e| e.to_string())
+    }
+
+    fn commit(&self, changes: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<(), String> {
+        self.0
+            .commit(changes.into_iter().map(|(key, value)| (0, key, value)))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Where an Ethereum transaction was executed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct TransactionMetadata<Hash> {
+    /// Hash of the Substrate block
+    pub substrate_block_hash: Hash,
+    /// Index of the transaction among the Ethereum transactions of the block
+    pub ethereum_index: u32,
+}
+
+/// Ethereum entries mapped to the Substrate block, kept to unmap them upon reorg.
+#[derive(Encode, Decode)]
+struct BlockEntries {
+    eth_block_hash: Option<H256>,
+    transactions: Vec<H256>,
+}
+
+/// Mapping of Ethereum transactions and blocks to the Substrate blocks of the canonical chain.
+pub struct MappingDb<B: BlockT> {
+    store: Arc<dyn KeyValueStore>,
+    _phantom: PhantomData<B>,
+}
+
+impl<B: BlockT> MappingDb<B> {
+    /// Create the mapping kept in the store given.
+    pub fn new(store: Arc<dyn KeyValueStore>) -> Self {
+        Self {
+            store,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Where the Ethereum transaction with the given hash was executed.
+    pub fn transaction(
+        &self,
+        eth_tx_hash: H256,
+    ) -> Result<Option<TransactionMetadata<B::Hash>>, String> {
+        self.read(&Self::key(TRANSACTION_PREFIX, eth_tx_hash))
+    }
+
+    /// Hash of the Substrate block the Ethereum block with the given hash was built for.
+    pub fn block_hash(&self, eth_block_hash: H256) -> Result<Option<B::Hash>, String> {
+        self.read(&Self::key(ETH_BLOCK_PREFIX, eth_block_hash))
+    }
+
+    /// Last Substrate block the mapping is synced to.
+    pub fn best(&self) -> Result<Option<B::Hash>, String> {
+        self.read(BEST_KEY)
+    }
+
+    /// Map the Ethereum block, if any, and the transactions given along with their indexes
+    /// among the Ethereum transactions of the block to the Substrate block,
+    /// which becomes the last block the mapping is synced to.
+    pub fn index_block(
+        &self,
+        hash: B::Hash,
+        eth_block_hash: Option<H256>,
+        transactions: Vec<(H256, u32)>,
+    ) -> Result<(), String> {
+        let mut changes: Vec<_> = transactions
+            .iter()
+            .map(|(tx_hash, ethereum_index)| {
+                let metadata = TransactionMetadata {
+                    substrate_block_hash: hash,
+                    ethereum_index: *ethereum_index,
+                };
+                (
+                    Self::key(TRANSACTION_PREFIX, tx_hash),
+                    Some(metadata.encode()),
+                )
+            })
+            .collect();
+        if let Some(eth_block_hash) = eth_block_hash {
+            changes.push((
+                Self::key(ETH_BLOCK_PREFIX, eth_block_hash),
+                Some(hash.encode()),
+            ));
+        }
+        let entries = BlockEntries {
+            eth_block_hash,
+            transactions: transactions
+                .into_iter()
+                .map(|(tx_hash, _)| tx_hash)
+                .collect(),
+        };
+        changes.push((Self::key(BLOCK_PREFIX, hash), Some(entries.encode())));
+        changes.push((BEST_KEY.to_vec(), Some(hash.encode())));
+        self.store.commit(changes)
+    }
+
+    /// Unmap the Ethereum block and transactions of the Substrate block
+    /// which left the canonical chain. Blocks not mapped are ignored.
+    pub fn unindex_block(&self, hash: B::Hash) -> Result<(), String> {
+        let block_key = Self::key(BLOCK_PREFIX, hash);
+        let Some(entries) = self.read::<BlockEntries>(&block_key)? else {
+            return Ok(());
+        };
+        let mut changes: Vec<_> = entries
+            .transactions
+            .iter()
+            .map(|tx_hash| (Self::key(TRANSACTION_PREFIX, tx_hash), None))
+            .collect();
+        if let Some(eth_block_hash) = entries.eth_block_hash {
+            changes.push((Self::key(ETH_BLOCK_PREFIX, eth_block_hash), None));
+        }
+        changes.push((block_key, None));
+        self.store.commit(changes)
+    }
+
+    /// Set the last Substrate block the mapping is synced to.
+    pub fn set_best(&self, hash: B::Hash) -> Result<(), String> {
+        self.store
+            .commit(vec![(BEST_KEY.to_vec(), Some(hash.encode()))])
+    }
+
+    fn read<T: Decode>(&self, key: &[u8]) -> Result<Option<T>, String> {
+        self.store
+            .get(key)?
+            .map(|value| T::decode(&mut &value[..]).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    fn key(prefix: &[u8], hash: impl Encode) -> Vec<u8> {
+        [prefix, &hash.encode()].concat()
+    }
+}
+
+/// Worker keeping the mapping in sync with the canonical chain:
+/// Ethereum blocks and transactions are taken from the runtime state of the blocks,
+/// blocks leaving the canonical chain upon reorg are unmapped.
+pub struct MappingSyncWorker<B: BlockT, C, BE> {
+    client: Arc<C>,
+    backend: Arc<BE>,
+    mapping: Arc<MappingDb<B>>,
+}
+
+impl<B, C, BE> MappingSyncWorker<B, C, BE>
+where
+    B: BlockT,
+    BE: Backend<B>,
+    C: ProvideRuntimeApi<B>
+        + HeaderBackend<B>
+        + HeaderMetadata<B, Error = BlockChainError>
+        + BlockchainEvents<B>
+        + 'static,
+    C::Api: EthinkAPI<B>,
+{
+    /// Create the worker, `backend` tells which blocks have their state pruned.
+    pub fn new(client: Arc<C>, backend: Arc<BE>, mapping: Arc<MappingDb<B>>) -> Self {
+        Self {
+            client,
+            backend,
+            mapping,
+        }
+    }
+
+    /// Catch up with the best block, then follow the new best blocks imported.
+    ///
+    /// Syncing is done with blocking client calls, which take long upon catching up,
+    /// so the worker should be spawned as a blocking task.
+    pub async fn run(self) {
+        self.sync_logged(self.client.info().best_hash);
+        let mut imports = self.client.import_notification_stream();
+        while let Some(notification) = imports.next().await {
+            if notification.is_new_best {
+                self.sync_logged(notification.hash);
+            }
+        }
+    }
+
+    fn sync_logged(&self, best: B::Hash) {
+        if let Err(e) = self.sync(best) {
+            log::error!(target: "ethink:mapping", "Failed to sync mapping to {:?}: {}", best, e);
+        }
+    }
+
+    /// Sync the mapping to the new best block: unmap the blocks retracted since the last sync,
+    /// and map the blocks enacted. Import notifications might be skipped, e.g. during major sync,
+    /// hence every block on the route gets mapped, not only the one notified of.
+    fn sync(&self, best: B::Hash) -> Result<(), String> {
+        let from = self
+            .mapping
+            .best()?
+            .unwrap_or(self.client.info().genesis_hash);
+        let route =
+            sp_blockchain::tree_route(&*self.client, from, best).map_err(|e| e.to_string())?;
+        for block in route.retracted() {
+            self.mapping.unindex_block(block.hash)?;
+        }
+        if !route.retracted().is_empty() {
+            self.mapping.set_best(route.common_block().hash)?;
+        }
+        for block in route.enacted() {
+            self.index_block(block.hash)?;
+        }
+        Ok(())
+    }
+
+    fn index_block(&self, hash: B::Hash) -> Result<(), String> {
+        let number = self
+            .client
+            .number(hash)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Unknown block {:?}", hash))?;
+        // State of the pruned blocks is gone, so there is nothing to map there.
+        // Any other failure is returned, so that the block gets retried upon the next sync
+        let (eth_block_hash, statuses) = if self.backend.have_state_at(hash, number) {
+            self.eth_block(hash)?
+                .map(|(eth_block_hash, statuses)| (Some(eth_block_hash), statuses))
+                .unwrap_or_default()
+        } else {
+            log::debug!(target: "ethink:mapping", "No state to map Ethereum block for {:?}", hash);
+            (None, Vec::new())
+        };
+        let transactions = statuses
+            .into_iter()
+            .map(|status| (status.transaction_hash, status.transaction_index))
+            .collect();
+        self.mapping.index_block(hash, eth_block_hash, transactions)
+    }
+
+    /// Hash of the Ethereum block emulated for the block, along with the statuses
+    /// of its transactions. Runtimes before version 2 of the API emulate no Ethereum blocks.
+    fn eth_block(&self, hash: B::Hash) -> Result<Option<(H256, Vec<TransactionStatus>)>, String> {
+        let api_version =
+            crate::api_version::<B, C>(&*self.client, hash).map_err(|e| e.message().to_string())?;
+        if api_version < 2 {
+            return Ok(None);
+        }
+        let api = self.client.runtime_api();
+        let Some(block) = api.current_block(hash).map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+        let statuses = api
+            .current_transaction_statuses(hash)
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        Ok(Some((block.header.hash(), statuses)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_runtime::{generic, traits::BlakeTwo256, OpaqueExtrinsic};
+
+    type Block = generic::Block<generic::Header<u32, BlakeTwo256>, OpaqueExtrinsic>;
+
+    #[test]
+    fn blocks_are_mapped_and_unmapped() {
+        let mapping = MappingDb::<Block>::new(Arc::new(InMemoryStore::default()));
+        let (block, fork) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        let (tx_a, tx_b) = (H256::repeat_byte(0xa), H256::repeat_byte(0xb));
+        let eth_block = H256::repeat_byte(0xe);
+
+        mapping
+            .index_block(block, Some(eth_block), vec![(tx_a, 0), (tx_b, 1)])
+            .unwrap();
+        assert_eq!(mapping.best(), Ok(Some(block)));
+        assert_eq!(mapping.block_hash(eth_block), Ok(Some(block)));
+        assert_eq!(
+            mapping.transaction(tx_b),
+            Ok(Some(TransactionMetadata {
+                substrate_block_hash: block,
+                ethereum_index: 1,
+            }))
+        );
+
+        // Reorg: the transaction gets into another block
+        mapping.unindex_block(block).unwrap();
+        mapping
+            .index_block(fork, Some(H256::zero()), vec![(tx_b, 0)])
+            .unwrap();
+        assert_eq!(mapping.best(), Ok(Some(fork)));
+        assert_eq!(mapping.block_hash(eth_block), Ok(None));
+        assert_eq!(mapping.transaction(tx_a), Ok(None));
+        assert_eq!(
+            mapping.transaction(tx_b),
+            Ok(Some(TransactionMetadata {
+                substrate_block_hash: fork,
+                ethereum_index: 0,
+            }))
+        );
+        // Unmapping a block twice is a no-op
+        assert_eq!(mapping.unindex_block(block), Ok(()));
+    }
+
+    #[test]
+    fn blocks_without_eth_block_are_not_mapped_to_it() {
+        let mapping = MappingDb::<Block>::new(Arc::new(InMemoryStore::default()));
+        let (block, other) = (H256::repeat_byte(1), H256::repeat_byte(2));
+
+        mapping.index_block(block, None, vec![]).unwrap();
+        mapping.index_block(other, None, vec![]).unwrap();
+        assert_eq!(mapping.best(), Ok(Some(other)));
+        assert_eq!(mapping.block_hash(H256::zero()), Ok(None));
+        // Unmapping leaves no Ethereum block behind either
+        mapping.unindex_block(other).unwrap();
+        assert_eq!(mapping.block_hash(H256::zero()), Ok(None));
+    }
+}
@@ -57,19 +57,44 @@ where
         ))
     }
 
-    // TODO implement
+    /// Minimum gas price the runtime accepts, as there is no fee market
     pub fn gas_price(&self) -> RpcResult<U256> {
-        Ok(U256::from(1000u32))
+        let hash = self.client.info().best_hash;
+        ensure_api_v2::<B, C>(&self.client, hash, "eth_gasPrice")?;
+
+        self.client
+            .runtime_api()
+            .gas_price(hash)
+            .map_err(|err| rpc_err!("Fetching runtime gas_price failed: {:?}", err))
     }
 
-    // TODO implement
     pub async fn storage_at(
         &self,
-        _address: H160,
-        _index: U256,
-        _number: Option<BlockNumber>,
+        address: H160,
+        index: U256,
+        number: Option<BlockNumber>,
     ) -> RpcResult<H256> {
-        Ok(H256::zero())
+        let hash = block_hash::<B, C>(&self.client, number).await?;
+        ensure_api_v2::<B, C>(&self.client, hash, "eth_getStorageAt")?;
+
+        let value = self
+            .client
+            .runtime_api()
+            .storage_at(hash, address, index)
+            .map_err(|err| rpc_err!("Fetching runtime storage_at failed: {:?}", err))?
+            .unwrap_or_default();
+        // Value is returned as stored, left-padded with zeros to a 32-byte word.
+        // Longer value does not fit into a slot, and cutting it would return wrong data
+        if value.len() > 32 {
+            return Err(rpc_err!(
+                "Value at the slot is {} bytes long, which exceeds 32 bytes",
+                (value.len())
+            ));
+        }
+        let mut word = H256::zero();
+        word[32 - value.len()..].copy_from_slice(&value);
+
+        Ok(word)
     }
 
     pub async fn transaction_count(
@@ -1,56 +1,123 @@
 use super::*;
+use crate::types::{executed_receipt, executed_transaction, pending_transaction};
+use sc_transaction_pool_api::InPoolTransaction;
 use sp_runtime::traits::UniqueSaturatedInto;
 
 impl<B, C, P> EthRPC<B, C, P>
 where
     B: BlockT<Hash = ep_eth::H256>,
     C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + 'static,
+    P: TransactionPool<Block = B> + 'static,
+    C::Api: EthinkAPI<B>,
 {
-    pub async fn transaction_by_hash(&self, _hash: H256) -> RpcResult<Option<Transaction>> {
-        Ok(None)
+    /// Find Ethereum transaction among the executed ones, or the ones awaiting in the pool.
+    pub async fn transaction_by_hash(&self, hash: H256) -> RpcResult<Option<Transaction>> {
+        let metadata = self
+            .mapping
+            .transaction(hash)
+            .map_err(|err| rpc_err!("Failed reading Ethereum mapping: {}", err))?;
+        match metadata {
+            Some(m) => self.transaction_in_block(m.substrate_block_hash, m.ethereum_index as usize),
+            None => Ok(self.pending_transaction(hash)),
+        }
     }
 
     pub async fn transaction_by_block_hash_and_index(
         &self,
-        _hash: H256,
-        _index: Index,
+        hash: H256,
+        index: Index,
     ) -> RpcResult<Option<Transaction>> {
-        Ok(None)
+        self.transaction_in_block(hash, index.value())
     }
 
     pub async fn transaction_by_block_number_and_index(
         &self,
-        _number: BlockNumber,
-        _index: Index,
+        number: BlockNumber,
+        index: Index,
     ) -> RpcResult<Option<Transaction>> {
-        Ok(None)
+        let hash = block_hash::<B, C>(&self.client, Some(number)).await?;
+        self.transaction_in_block(hash, index.value())
     }
 
-    // NOTE: tx_hash in Polkadot is not unique... block_hash ++ tx_hash is unique.
-    // Anyway for now we generate a fake receipt just to provide some response to MetaMask queries.
-    // TODO this needs to get implemented
-    // BUG not working with alloy
+    /// Ethereum transaction with the given index among the ones executed in the block,
+    /// taken from the Ethereum block emulated by the runtime for it.
+    fn transaction_in_block(&self, hash: H256, index: usize) -> RpcResult<Option<Transaction>> {
+        let Some(number) = self
+            .client
+            .number(hash)
+            .map_err(|err| rpc_err!("Failed fetching block number by hash: {:?}", err))?
+        else {
+            return Ok(None);
+        };
+        let Some((eth_block, statuses)) = self.eth_block_by_hash(hash)? else {
+            return Ok(None);
+        };
+        // Sender, recipient and the contract created are reported by the runtime upon execution
+        let Some((tx, status)) = eth_block.transactions.into_iter().zip(statuses).nth(index) else {
+            return Ok(None);
+        };
+        let number: u128 = number.unique_saturated_into();
+
+        Ok(Some(executed_transaction(
+            tx,
+            status,
+            Some(hash),
+            Some(number.into()),
+        )))
+    }
+
+    /// Ethereum transaction awaiting in the pool, either ready or waiting for its nonce, if any.
+    fn pending_transaction(&self, hash: H256) -> Option<Transaction> {
+        let futures = self.pool.futures();
+        self.pool
+            .ready()
+            .filter_map(|xt| (self.decode)(xt.data()))
+            .chain(futures.iter().filter_map(|xt| (self.decode)(xt.data())))
+            .find(|tx| tx.hash() == hash)
+            .map(pending_transaction)
+    }
+
+    /// Receipt of the executed Ethereum transaction, built out of the receipts
+    /// and transaction statuses the runtime keeps for the block it was executed in.
     pub async fn transaction_receipt(&self, hash: H256) -> RpcResult<Option<Receipt>> {
-        let transaction_hash = Some(hash);
-        // Always answer as if:
-        // the tx was successfully included
-        let status_code = Some(1u64.into());
-        // into the recent block
-        let block_hash = Some(self.client.info().best_hash);
-        let block_number = Some(
-            UniqueSaturatedInto::<u128>::unique_saturated_into(self.client.info().best_number)
-                .into(),
-        );
-        // with tx_id = 1
-        let transaction_index = Some(1.into());
-
-        Ok(Some(Receipt {
-            transaction_hash,
-            transaction_index,
-            status_code,
+        let Some(metadata) = self
+            .mapping
+            .transaction(hash)
+            .map_err(|err| rpc_err!("Failed reading Ethereum mapping: {}", err))?
+        else {
+            return Ok(None);
+        };
+        let block_hash = metadata.substrate_block_hash;
+        let Some(number) = self
+            .client
+            .number(block_hash)
+            .map_err(|err| rpc_err!("Failed fetching block number by hash: {:?}", err))?
+        else {
+            return Ok(None);
+        };
+        let Some((eth_block, statuses)) = self.eth_block_by_hash(block_hash)? else {
+            return Ok(None);
+        };
+        let receipts = self
+            .client
+            .runtime_api()
+            .current_receipts(block_hash)
+            .map_err(|err| rpc_err!("Fetching runtime current_receipts failed: {:?}", err))?
+            .unwrap_or_default();
+        let index = metadata.ethereum_index as usize;
+        // The runtime is the source of truth on the transaction, rather than the mapping
+        let status = statuses.get(index).filter(|s| s.transaction_hash == hash);
+        let (Some(tx), Some(status)) = (eth_block.transactions.get(index), status) else {
+            return Ok(None);
+        };
+        let number: u128 = number.unique_saturated_into();
+
+        Ok(executed_receipt(
+            tx,
+            status.clone(),
+            &receipts,
             block_hash,
-            block_number,
-            ..Default::default()
-        }))
+            number.into(),
+        ))
     }
 }
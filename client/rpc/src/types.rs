@@ -1,8 +1,9 @@
 //! We keep custom types here and not in rpc-core,
 //! for we might later switch to fc-rpc-core.
 use ep_eth::{
-    AccountId20, EthereumSignature, LegacyTransactionMessage, TransactionSignature, H160, H256,
-    H64, U256,
+    AccountId20, Block as EthBlock, EIP658ReceiptData, EthTransaction, EthereumSignature,
+    LegacyTransactionMessage, Receipt as EthReceipt, StorageDeposit, TransactionAction,
+    TransactionSignature, TransactionStatus, H160, H256, H64, U256,
 };
 
 // Substrate
@@ -11,7 +12,7 @@ use sp_keystore::KeystorePtr;
 use sp_runtime::traits::{Block as BlockT, Header, UniqueSaturatedInto};
 
 use crate::{BTreeMap, ETHINK_KEYTYPE_ID};
-use ethink_rpc_core::types::Header as EthHeader;
+use ethink_rpc_core::types::{BlockTransactions, Header as EthHeader, Log, Receipt, Transaction};
 
 pub use ethink_rpc_core::types::{Block as EthereumBlock, RichBlock};
 pub use sp_runtime::generic::SignedBlock;
@@ -30,8 +31,8 @@ impl<B: BlockT> SubstrateBlock<B> {
 }
 
 impl<B: BlockT<Hash = H256>> From<SubstrateBlock<B>> for RichBlock {
-    // Generate dumb EthBlock with empty tx list,
-    // from the given substrate block
+    // Generate EthBlock with empty tx list from the given substrate block,
+    // transactions get filled in with `with_eth_transactions()`
     fn from(b: SubstrateBlock<B>) -> Self {
         let h = b.0.header();
 
@@ -61,6 +62,149 @@ impl<B: BlockT<Hash = H256>> From<SubstrateBlock<B>> for RichBlock {
     }
 }
 
+/// Fill in the RichBlock with Ethereum transactions executed in it,
+/// taken from the Ethereum block emulated by the runtime.
+pub fn with_eth_transactions(
+    mut rich: RichBlock,
+    eth_block: EthBlock,
+    statuses: Vec<TransactionStatus>,
+    full: bool,
+) -> RichBlock {
+    let header = &mut rich.inner.header;
+    header.receipts_root = eth_block.header.receipts_root;
+    header.logs_bloom = eth_block.header.logs_bloom;
+    header.gas_used = eth_block.header.gas_used;
+    header.gas_limit = eth_block.header.gas_limit;
+
+    rich.inner.transactions = if full {
+        let (block_hash, block_number) = (header.hash, header.number);
+        BlockTransactions::Full(
+            eth_block
+                .transactions
+                .into_iter()
+                .zip(statuses)
+                .map(|(tx, status)| executed_transaction(tx, status, block_hash, block_number))
+                .collect(),
+        )
+    } else {
+        BlockTransactions::Hashes(statuses.iter().map(|s| s.transaction_hash).collect())
+    };
+
+    rich
+}
+
+/// Ethereum transaction executed in the block given.
+pub fn executed_transaction(
+    tx: EthTransaction,
+    status: TransactionStatus,
+    block_hash: Option<H256>,
+    block_number: Option<U256>,
+) -> Transaction {
+    Transaction {
+        block_hash,
+        block_number,
+        transaction_index: Some(status.transaction_index.into()),
+        from: status.from,
+        to: status.to,
+        creates: status.contract_address,
+        ..Transaction::from(tx)
+    }
+}
+
+/// Receipt of the Ethereum transaction with the given status, out of the receipts
+/// of all the Ethereum transactions executed in the block given.
+pub fn executed_receipt(
+    tx: &EthTransaction,
+    status: TransactionStatus,
+    receipts: &[EthReceipt],
+    block_hash: H256,
+    block_number: U256,
+) -> Option<Receipt> {
+    let index = status.transaction_index as usize;
+    let receipt = receipt_data(receipts.get(index)?);
+    let previous = &receipts[..index];
+    // Receipts carry gas used by the block so far, hence it is the difference with the previous one
+    let gas_used = receipt.used_gas.saturating_sub(
+        previous
+            .last()
+            .map(|r| receipt_data(r).used_gas)
+            .unwrap_or_default(),
+    );
+    let first_log_index: usize = previous.iter().map(|r| receipt_data(r).logs.len()).sum();
+    let (transaction_type, effective_gas_price) = match tx {
+        EthTransaction::Legacy(t) => (0u64, t.gas_price),
+        EthTransaction::EIP2930(t) => (1, t.gas_price),
+        // There is no base fee, so the priority fee is paid within the max fee
+        EthTransaction::EIP1559(t) => (2, t.max_priority_fee_per_gas.min(t.max_fee_per_gas)),
+    };
+    let logs = receipt
+        .logs
+        .iter()
+        .enumerate()
+        .map(|(i, log)| Log {
+            address: log.address,
+            topics: log.topics.clone(),
+            data: log.data.clone().into(),
+            block_hash: Some(block_hash),
+            block_number: Some(block_number),
+            transaction_hash: Some(status.transaction_hash),
+            transaction_index: Some(index.into()),
+            log_index: Some((first_log_index + i).into()),
+            transaction_log_index: Some(i.into()),
+            removed: false,
+        })
+        .collect();
+    let (storage_deposit_charged, storage_deposit_refunded) = match status.storage_deposit {
+        StorageDeposit::Charge(amount) => (Some(amount), None),
+        StorageDeposit::Refund(amount) => (None, Some(amount)),
+    };
+
+    Some(Receipt {
+        transaction_hash: Some(status.transaction_hash),
+        transaction_index: Some(index.into()),
+        block_hash: Some(block_hash),
+        from: Some(status.from),
+        to: status.to,
+        block_number: Some(block_number),
+        cumulative_gas_used: receipt.used_gas,
+        gas_used: Some(gas_used),
+        contract_address: status.contract_address,
+        logs,
+        state_root: None,
+        logs_bloom: receipt.logs_bloom,
+        status_code: Some(receipt.status_code.into()),
+        effective_gas_price,
+        transaction_type: transaction_type.into(),
+        storage_deposit_charged,
+        storage_deposit_refunded,
+    })
+}
+
+fn receipt_data(receipt: &EthReceipt) -> &EIP658ReceiptData {
+    match receipt {
+        EthReceipt::Legacy(d) | EthReceipt::EIP2930(d) | EthReceipt::EIP1559(d) => d,
+    }
+}
+
+/// Ethereum transaction awaiting in the pool, hence with no block fields set.
+pub fn pending_transaction(tx: EthTransaction) -> Transaction {
+    let from = ep_eth::recover_signer(&tx).unwrap_or_default();
+    let action = match &tx {
+        EthTransaction::Legacy(t) => &t.action,
+        EthTransaction::EIP2930(t) => &t.action,
+        EthTransaction::EIP1559(t) => &t.action,
+    };
+    let to = match action {
+        TransactionAction::Call(to) => Some(*to),
+        TransactionAction::Create => None,
+    };
+    Transaction {
+        from,
+        to,
+        ..Transaction::from(tx)
+    }
+}
+
 /// Ethereum transaction signer with keypair stored in node's keystore
 pub struct EthereumSigner {
     keystore: KeystorePtr,
@@ -68,6 +212,7 @@ pub struct EthereumSigner {
 }
 impl EthereumSigner {
     pub fn try_sign(&self, msg: LegacyTransactionMessage) -> Result<TransactionSignature, String> {
+        let chain_id = msg.chain_id;
         let sig = self
             .keystore
             .ecdsa_sign_prehashed(
@@ -79,9 +224,9 @@ impl EthereumSigner {
             .expect("we checked that keystore contains needed secret upon signer construction; qed")
             .map_err(|_| "Failed to sign tx".to_string())?;
 
-        let sig: Option<TransactionSignature> = EthereumSignature::new(sig).into();
-
-        sig.ok_or("signer generated invalid signature".to_string())
+        EthereumSignature::new(sig)
+            .into_tx_signature(chain_id)
+            .ok_or("signer generated invalid signature".to_string())
     }
 }
 
@@ -3,7 +3,7 @@
 
 #[ink::contract(env = EthinkEnvironment)]
 mod erc20 {
-    use alloy_sol_types::{sol, SolType, sol_data::{String,Uint}};
+    use alloy_sol_types::{sol, Revert, SolError, SolType, sol_data::{String,Uint}};
     use ink::storage::Mapping;
 
     sol! {
@@ -140,7 +140,9 @@ mod erc20 {
             ink::env::debug_println!("Hello from contract:transfer(). Input is: {:x?}", &input);
             let (to, value) =
                 <(Address, Amount)>::abi_decode_params(input.as_slice(), false).unwrap();
-            self.transfer_from_to(&from, &to, value).expect("ink: insufficient allowance or balance");
+            if let Err(error) = self.transfer_from_to(&from, &to, value) {
+                Self::revert(error)
+            }
             Uint::<8>::abi_encode(&(true as u8)).try_into().expect("ink: result value length is wrong")
         }
 
@@ -246,6 +248,22 @@ mod erc20 {
             Ok(())
         }
 
+        /// Reverts with the error encoded as Solidity `Error(string)`,
+        /// so that Ethereum tooling could decode the reason.
+        fn revert(error: Error) -> ! {
+            let reason = match error {
+                Error::InsufficientBalance => "insufficient balance",
+                Error::InsufficientAllowance => "insufficient allowance",
+            };
+            // Reason shorter than 32 bytes is encoded in 100 bytes:
+            // selector, offset and length of the string, and the string padded to 32 bytes
+            let data: [u8; 100] = Revert { reason: reason.into() }
+                .abi_encode()
+                .try_into()
+                .expect("ink: revert data length is wrong");
+            ink::env::return_value(ink::env::ReturnFlags::REVERT, &data)
+        }
+
         fn balance_of_internal(&self, owner: AccountId) -> Balance {
             self.balances.get(owner).unwrap_or_default()
         }
@@ -0,0 +1,39 @@
+use super::*;
+
+/// Conversion between Ethereum amounts in wei (18 decimals) and native balance.
+pub trait BalanceConverter<Balance> {
+    /// Native balance for the amount of wei given.
+    /// Returns `None` if the amount has dust unrepresentable in native balance,
+    /// or overflows native balance type.
+    fn into_native(wei: U256) -> Option<Balance>;
+    /// Amount of wei the native balance given is worth.
+    fn into_wei(balance: Balance) -> U256;
+}
+
+/// Converter for native balance with `Decimals` decimals, which should be at most 18.
+/// A unit of native balance is worth `10^(18 - Decimals)` wei.
+pub struct DecimalsConverter<Decimals>(PhantomData<Decimals>);
+
+impl<Decimals: Get<u32>> DecimalsConverter<Decimals> {
+    fn wei_per_unit() -> U256 {
+        U256::exp10(18u32.saturating_sub(Decimals::get()) as usize)
+    }
+}
+
+impl<B, Decimals> BalanceConverter<B> for DecimalsConverter<Decimals>
+where
+    B: TryFrom<U256> + Into<U256>,
+    Decimals: Get<u32>,
+{
+    fn into_native(wei: U256) -> Option<B> {
+        let (native, dust) = wei.div_mod(Self::wei_per_unit());
+        if !dust.is_zero() {
+            return None;
+        }
+        native.try_into().ok()
+    }
+
+    fn into_wei(balance: B) -> U256 {
+        balance.into().saturating_mul(Self::wei_per_unit())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    BalanceOf, Call, Config, DispatchInfo, Dispatchable, EthTransaction, OriginFor, Pallet,
-    PostDispatchInfo, RawOrigin, U256,
+    Call, Config, DispatchInfo, Dispatchable, EthTransaction, OriginFor, Pallet, PostDispatchInfo,
+    RawOrigin, U256,
 };
 use ep_eth::{
     AccountId20, LegacyTransaction, LegacyTransactionMessage, TransactionAction,
@@ -23,7 +23,6 @@ const ALITH_KEY: [u8; 32] = [
      T::AccountId: From<sp_core::H160> + AsRef<[u8]> + Into<sp_core::H160>,
      T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
      OriginFor<T>: Into<Result<RawOrigin, OriginFor<T>>>,
-     BalanceOf<T>: TryFrom<sp_core::U256>,
      T::RuntimeOrigin: From<RawOrigin>,
 )]
 mod benchmarks {
@@ -0,0 +1,36 @@
+use super::*;
+
+/// Limit on the storage deposit an Ethereum transaction can take from the sender's balance.
+/// Ethereum transactions carry no such limit, hence it is derived from what they do carry.
+pub trait DepositLimit<Balance> {
+    /// Storage deposit limit for the transaction with the fee budget given,
+    /// i.e. the maximum fee the transaction could cost. `None` means no limit.
+    fn deposit_limit(fee_budget: Balance) -> Option<Balance>;
+}
+
+/// No limit on storage deposit.
+impl<Balance> DepositLimit<Balance> for () {
+    fn deposit_limit(_fee_budget: Balance) -> Option<Balance> {
+        None
+    }
+}
+
+/// Storage deposit is limited by the fee budget of the transaction,
+/// so that the sender can't be charged for storage more than for gas.
+pub struct FeeBudgetDepositLimit;
+
+impl<Balance> DepositLimit<Balance> for FeeBudgetDepositLimit {
+    fn deposit_limit(fee_budget: Balance) -> Option<Balance> {
+        Some(fee_budget)
+    }
+}
+
+/// Storage deposit is limited by a fixed amount,
+/// regardless of the fee budget of the transaction.
+pub struct ConstDepositLimit<Limit>(PhantomData<Limit>);
+
+impl<Balance, Limit: Get<Balance>> DepositLimit<Balance> for ConstDepositLimit<Limit> {
+    fn deposit_limit(_fee_budget: Balance) -> Option<Balance> {
+        Some(Limit::get())
+    }
+}
@@ -1,130 +1,441 @@
 use super::*;
+use frame_support::{
+    storage::child::{self, ChildInfo},
+    StorageHasher,
+};
+use scale_codec::Compact;
+
+/// Outcome of the contract execution, independent of the contracts engine.
+#[derive(Clone, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct ExecResult {
+    /// Data returned by the contract, or the revert data if it reverted
+    pub output: Vec<u8>,
+    /// Gas spent on the execution
+    pub gas_used: U256,
+    /// Gas the execution needs to pass, which could exceed the gas spent, e.g. due to refunds
+    pub gas_required: U256,
+    /// Ethereum logs out of the events emitted by the contract
+    pub logs: Vec<Log>,
+    /// Storage deposit charged from, or refunded to the caller
+    pub storage_deposit: StorageDeposit,
+    /// Whether the contract reverted, with all its state changes rolled back
+    pub reverted: bool,
+}
+
+/// Contract execution which failed before the contract could return or revert,
+/// e.g. by running out of gas. The gas spent until then is still to be paid.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct ExecError {
+    pub error: DispatchError,
+    pub gas_used: U256,
+}
+
+/// Reason for a dry run of the contract execution to fail.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub enum DryRunError {
+    /// Contract reverted with the data given
+    Reverted(Vec<u8>),
+    /// Execution failed before the contract could return or revert
+    Failed(DispatchError),
+}
+
+impl From<DispatchError> for DryRunError {
+    fn from(e: DispatchError) -> Self {
+        DryRunError::Failed(e)
+    }
+}
 
 /// Provider of the contracts functionality.
 /// Currently this is pallet_contracts, though might be changed in the future.
 pub trait Executor<T: pallet::Config> {
-    type ExecResult;
-
+    /// Code of the contract at the address given, as it was uploaded
     fn code_at(address: &T::AccountId) -> Option<Vec<u8>>;
+    /// Hash of the code of the contract at the address given
+    fn code_hash_at(address: &T::AccountId) -> Option<H256>;
+    /// Value stored by the contract under the key given
+    fn storage_at(address: &T::AccountId, key: Vec<u8>) -> Option<Vec<u8>>;
+    /// Store the value under the key given in the contract storage, or remove it if `None`.
+    /// Bypasses storage deposit accounting, hence only meant for state overrides of dry runs.
+    fn set_storage(
+        address: &T::AccountId,
+        key: Vec<u8>,
+        value: Option<Vec<u8>>,
+    ) -> Result<(), DispatchError>;
+    /// Remove all the values stored by the contract.
+    /// Same as [`Self::set_storage`], only meant for state overrides of dry runs.
+    fn clear_storage(address: &T::AccountId) -> Result<(), DispatchError>;
+    /// Upload the code given on behalf of `uploader`, and make it the code of the contract.
+    /// The uploader pays the deposit for the code uploaded.
+    fn replace_code(
+        uploader: &T::AccountId,
+        address: &T::AccountId,
+        code: Vec<u8>,
+    ) -> Result<(), DispatchError>;
 
     /// Check if AccountId is owned by a contract
     fn is_contract(who: &T::AccountId) -> bool;
-    /// Construct proper runtime call for the input provided
-    fn build_call(
-        to: T::AccountId,
-        value: U256,
-        data: Vec<u8>,
-        gas_limit: U256,
-    ) -> Option<T::RuntimeCall>;
-    /// Call contract
+    /// Call the contract, spending at most `gas_limit`,
+    /// and reserving at most `storage_deposit_limit` for the storage taken, if set.
+    /// Used both for Ethereum transactions and dry runs of them.
     fn call(
-        from: T::AccountId,
+        from: &T::AccountId,
         to: T::AccountId,
-        data: Vec<u8>,
         value: BalanceOf<T>,
-        gas_limit: Weight,
-    ) -> Self::ExecResult;
-    /// Estimate gas
-    fn gas_estimate(
-        from: T::AccountId,
-        to: T::AccountId,
         data: Vec<u8>,
+        gas_limit: U256,
+        storage_deposit_limit: Option<BalanceOf<T>>,
+    ) -> Result<ExecResult, ExecError>;
+    /// Instantiate contract (CREATE) from the input provided, spending at most `gas_limit`,
+    /// and reserving at most `storage_deposit_limit` for the storage taken, if set.
+    /// Returns address of the contract instantiated along with the result of its constructor.
+    fn instantiate(
+        deployer: &T::AccountId,
         value: BalanceOf<T>,
-        gas_limit: Weight,
-    ) -> Result<U256, DispatchError>;
+        input: Vec<u8>,
+        salt: Vec<u8>,
+        gas_limit: U256,
+        storage_deposit_limit: Option<BalanceOf<T>>,
+    ) -> Result<(T::AccountId, ExecResult), ExecError>;
+}
+
+const WASM_PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+/// Split CREATE transaction input into the wasm blob and the constructor input data.
+///
+/// Ethereum tooling sends contract code with constructor data appended to it. As a wasm module
+/// does not tell where it ends, the code is expected to be SCALE-encoded, i.e. prefixed with its
+/// compact-encoded length, and the constructor data follows it as is: `(code, data)`.
+pub fn split_wasm_input(input: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut data = input;
+    let code = Vec::<u8>::decode(&mut data).ok()?;
+    if !code.starts_with(&WASM_PREAMBLE) {
+        return None;
+    }
+
+    Some((code, data.to_vec()))
+}
+
+/// Original code uploaded to `pallet_contracts`, by its code hash.
+#[frame_support::storage_alias]
+type PristineCode<T: pallet_contracts::Config> = StorageMap<
+    pallet_contracts::Pallet<T>,
+    frame_support::Identity,
+    <T as frame_system::Config>::Hash,
+    Vec<u8>,
+>;
+
+/// Code uploaded to `pallet_contracts` with the hash given.
+pub fn contracts_pristine_code<T: pallet_contracts::Config>(
+    code_hash: &T::Hash,
+) -> Option<Vec<u8>> {
+    PristineCode::<T>::get(code_hash)
+}
+
+/// Leading part of `pallet_contracts` contract info, up to the id of its storage child trie.
+#[derive(Encode, Decode)]
+struct ContractTrieId {
+    trie_id: Vec<u8>,
+}
+
+/// Info of `pallet_contracts` contracts, of which only the storage child trie id is read.
+///
+/// `pallet_contracts` has no public API to write contract storage, which state overrides need,
+/// so we locate the child trie of the contract ourselves. This relies on the layout of its
+/// private `ContractInfoOf` storage, as of `polkadot-sdk` 1.12: a `Twox64Concat` map by account,
+/// with `ContractInfo` starting with `trie_id`. Should an upgrade change that, the
+/// `state_overrides_are_applied_and_rolled_back` test fails, as it reads the values stored here
+/// back through the public `pallet_contracts::Pallet::get_storage`.
+#[frame_support::storage_alias]
+type ContractInfoOf<T: pallet_contracts::Config> = StorageMap<
+    pallet_contracts::Pallet<T>,
+    frame_support::Twox64Concat,
+    <T as frame_system::Config>::AccountId,
+    ContractTrieId,
+>;
+
+/// Storage child trie of the `pallet_contracts` contract at the address given.
+fn contracts_child_info<T: pallet_contracts::Config>(
+    address: &T::AccountId,
+) -> Result<ChildInfo, DispatchError> {
+    ContractInfoOf::<T>::get(address)
+        .map(|info| ChildInfo::new_default(&info.trie_id))
+        .ok_or_else(|| pallet_contracts::Error::<T>::ContractNotFound.into())
+}
+
+/// Store the value under the key given in the storage of the `pallet_contracts` contract,
+/// or remove it if `None`. Keys are hashed the same way as variable-sized keys of contracts.
+pub fn contracts_set_storage<T: pallet_contracts::Config>(
+    address: &T::AccountId,
+    key: &[u8],
+    value: Option<Vec<u8>>,
+) -> Result<(), DispatchError> {
+    let child_info = contracts_child_info::<T>(address)?;
+    let key = frame_support::Blake2_128Concat::hash(key);
+    match value {
+        Some(value) => child::put_raw(&child_info, &key, &value),
+        None => child::kill(&child_info, &key),
+    }
+
+    Ok(())
+}
+
+/// Remove all the values stored by the `pallet_contracts` contract.
+pub fn contracts_clear_storage<T: pallet_contracts::Config>(
+    address: &T::AccountId,
+) -> Result<(), DispatchError> {
+    let child_info = contracts_child_info::<T>(address)?;
+    let _ = child::clear_storage(&child_info, None, None);
+
+    Ok(())
+}
+
+/// Storage deposit in wei out of the one reported by `pallet_contracts`.
+pub fn contracts_storage_deposit<T: pallet::Config>(
+    deposit: pallet_contracts::StorageDeposit<BalanceOf<T>>,
+) -> StorageDeposit {
+    match deposit {
+        pallet_contracts::StorageDeposit::Charge(amount) => {
+            StorageDeposit::Charge(T::BalanceConverter::into_wei(amount))
+        }
+        pallet_contracts::StorageDeposit::Refund(amount) => {
+            StorageDeposit::Refund(T::BalanceConverter::into_wei(amount))
+        }
+    }
+}
+
+/// Events deposited in the current block, as they are stored by `frame_system`.
+#[frame_support::storage_alias]
+type Events<T: frame_system::Config> = StorageValue<
+    frame_system::Pallet<T>,
+    Vec<
+        frame_system::EventRecord<
+            <T as frame_system::Config>::RuntimeEvent,
+            <T as frame_system::Config>::Hash,
+        >,
+    >,
+    ValueQuery,
+>;
+
+/// Position in the events of the current block, from which the events deposited afterwards
+/// could be read without decoding all the preceding ones.
+#[derive(Clone, Copy, RuntimeDebug)]
+pub struct EventsCursor {
+    /// Number of events deposited so far
+    count: u32,
+    /// Length of the encoded events, along with their compact-encoded number
+    len: u32,
+}
+
+impl EventsCursor {
+    /// Cursor pointing right past the last event deposited so far.
+    pub fn current<T: frame_system::Config>() -> Self {
+        Self {
+            count: System::<T>::event_count(),
+            len: sp_io::storage::read(&Events::<T>::hashed_key(), &mut [], 0).unwrap_or_default(),
+        }
+    }
+
+    /// Events deposited since the cursor was taken.
+    ///
+    /// Events are appended to storage, so the new ones follow the old ones right away,
+    /// while the number of events in front of them could take more bytes than it did.
+    fn events_since<T: frame_system::Config>(
+        &self,
+    ) -> Vec<frame_system::EventRecord<T::RuntimeEvent, T::Hash>> {
+        let count = System::<T>::event_count();
+        let Some(new) = count.checked_sub(self.count).filter(|n| *n > 0) else {
+            return Vec::new();
+        };
+        let prefix_len = |n: u32| Compact(n).encoded_size() as u32;
+        let old_events_len = self.len.saturating_sub(prefix_len(self.count));
+        let offset = prefix_len(count) + old_events_len;
+        let key = Events::<T>::hashed_key();
+        let Some(len) = sp_io::storage::read(&key, &mut [], offset) else {
+            return Vec::new();
+        };
+        let mut buf = sp_std::vec![0; len as usize];
+        sp_io::storage::read(&key, &mut buf, offset);
+
+        let mut input = &buf[..];
+        (0..new)
+            .map_while(|_| Decode::decode(&mut input).ok())
+            .collect()
+    }
+}
+
+/// Ethereum logs out of the events emitted by `pallet_contracts` contracts,
+/// since the events cursor given was taken.
+pub fn contracts_logs<T>(cursor: EventsCursor) -> Vec<Log>
+where
+    T: pallet::Config + pallet_contracts::Config,
+    T::AccountId: Into<H160>,
+    <T as frame_system::Config>::RuntimeEvent: TryInto<pallet_contracts::Event<T>>,
+{
+    cursor
+        .events_since::<T>()
+        .into_iter()
+        .filter_map(|record| match record.event.try_into() {
+            Ok(pallet_contracts::Event::ContractEmitted { contract, data }) => {
+                let topics = record.topics.iter().map(Pallet::<T>::to_h256).collect();
+                T::LogMapping::to_log(contract.into(), topics, data)
+            }
+            _ => None,
+        })
+        .collect()
 }
 
 #[macro_export]
 macro_rules! impl_executor {
     ($conf:ident,$contr:ident) => {
-        use pallet_contracts::ContractExecResult;
-        use pallet_ethink::{BalanceOf, Executor};
-
-        impl Executor<$conf> for $contr {
-            type ExecResult = ContractExecResult<
-                BalanceOf<$conf>,
-                frame_system::EventRecord<
-                    <$conf as frame_system::Config>::RuntimeEvent,
-                    <$conf as frame_system::Config>::Hash,
-                >,
-            >;
-            // NOTE this returns code hash instead of code/
-            // To get the code, a getter should be added for the pallet storage.
+        impl pallet_ethink::Executor<$conf> for $contr {
             fn code_at(address: &<$conf as frame_system::Config>::AccountId) -> Option<Vec<u8>> {
-                Self::code_hash(address).map(|h| h.to_fixed_bytes().to_vec())
+                Self::code_hash(address)
+                    .and_then(|h| pallet_ethink::contracts_pristine_code::<$conf>(&h))
             }
 
-            fn is_contract(who: &<$conf as frame_system::Config>::AccountId) -> bool {
-                Self::code_hash(who).is_some()
+            fn code_hash_at(
+                address: &<$conf as frame_system::Config>::AccountId,
+            ) -> Option<sp_core::H256> {
+                Self::code_hash(address).map(|h| sp_core::H256::from_slice(h.as_ref()))
             }
 
-            /// Estimate gas
-            fn gas_estimate(
-                from: <$conf as frame_system::Config>::AccountId,
-                to: <$conf as frame_system::Config>::AccountId,
-                data: Vec<u8>,
-                value: BalanceOf<$conf>,
-                gas_limit: Weight,
-            ) -> Result<U256, DispatchError> {
-                if Self::is_contract(&to) {
-                    let res = <Self as Executor<$conf>>::call(from, to, data, value, gas_limit);
-                    // ensure successful execution
-                    let _ = res.result?;
-                    // get consumed gas
-                    let gas_consumed = res.gas_consumed.ref_time();
-                    Ok(gas_consumed.into())
-                } else {
-                    // Standard base fee
-                    Ok(U256::from(pallet_ethink::ETH_BASE_GAS_FEE))
-                }
+            fn storage_at(
+                address: &<$conf as frame_system::Config>::AccountId,
+                key: Vec<u8>,
+            ) -> Option<Vec<u8>> {
+                Self::get_storage(address.clone(), key).ok().flatten()
             }
 
-            fn build_call(
-                to: <$conf as frame_system::Config>::AccountId,
-                value: U256,
-                data: Vec<u8>,
-                gas_limit: U256,
-            ) -> Option<<$conf as frame_system::Config>::RuntimeCall> {
-                let dest = sp_runtime::MultiAddress::Id(to.into());
-                let value = value.try_into().ok()?;
-                let gas_limit = gas_limit.try_into().ok()?;
-                let gas_limit = Weight::from_parts(gas_limit, u64::MAX);
-
-                Some(if Self::is_contract(&to) {
-                    pallet_contracts::Call::<$conf>::call {
-                        dest,
-                        value,
-                        data,
-                        gas_limit,
-                        storage_deposit_limit: None,
-                    }
-                    .into()
-                } else {
-                    // NOTE basically pallet-contracts can do this for us, as its call() extrinsic
-                    // handles the call made to user account in a similar fashion.
-                    // However, we keep this logic here not to rely on particular executor pallet too much.
-                    pallet_balances::Call::<$conf>::transfer_allow_death { dest, value }.into()
-                })
+            fn set_storage(
+                address: &<$conf as frame_system::Config>::AccountId,
+                key: Vec<u8>,
+                value: Option<Vec<u8>>,
+            ) -> Result<(), sp_runtime::DispatchError> {
+                pallet_ethink::contracts_set_storage::<$conf>(address, &key, value)
+            }
+
+            fn clear_storage(
+                address: &<$conf as frame_system::Config>::AccountId,
+            ) -> Result<(), sp_runtime::DispatchError> {
+                pallet_ethink::contracts_clear_storage::<$conf>(address)
+            }
+
+            fn replace_code(
+                uploader: &<$conf as frame_system::Config>::AccountId,
+                address: &<$conf as frame_system::Config>::AccountId,
+                code: Vec<u8>,
+            ) -> Result<(), sp_runtime::DispatchError> {
+                let code_hash = Self::bare_upload_code(
+                    uploader.clone(),
+                    code,
+                    None,
+                    pallet_contracts::Determinism::Enforced,
+                )?
+                .code_hash;
+                pallet_contracts::Pallet::<$conf>::set_code(
+                    frame_system::RawOrigin::<<$conf as frame_system::Config>::AccountId>::Root
+                        .into(),
+                    <<$conf as frame_system::Config>::Lookup as sp_runtime::traits::StaticLookup>::unlookup(
+                        address.clone(),
+                    ),
+                    code_hash,
+                )
+            }
+
+            fn is_contract(who: &<$conf as frame_system::Config>::AccountId) -> bool {
+                Self::code_hash(who).is_some()
             }
 
             fn call(
-                from: <$conf as frame_system::Config>::AccountId,
+                from: &<$conf as frame_system::Config>::AccountId,
                 to: <$conf as frame_system::Config>::AccountId,
+                value: pallet_ethink::BalanceOf<$conf>,
                 data: Vec<u8>,
-                value: BalanceOf<$conf>,
-                gas_limit: Weight,
-            ) -> Self::ExecResult {
-                Self::bare_call(
-                    from,
+                gas_limit: sp_core::U256,
+                storage_deposit_limit: Option<pallet_ethink::BalanceOf<$conf>>,
+            ) -> Result<pallet_ethink::ExecResult, pallet_ethink::ExecError> {
+                let events = pallet_ethink::EventsCursor::current::<$conf>();
+                let res = Self::bare_call(
+                    from.clone(),
                     to,
                     value,
-                    gas_limit,
-                    None,
+                    pallet_ethink::Pallet::<$conf>::gas_to_weight(gas_limit),
+                    storage_deposit_limit,
                     data,
-                    CONTRACTS_DEBUG_OUTPUT,
-                    CONTRACTS_EVENTS,
+                    pallet_contracts::DebugInfo::Skip,
+                    pallet_contracts::CollectEvents::Skip,
                     pallet_contracts::Determinism::Enforced,
-                )
+                );
+                let gas_used = pallet_ethink::Pallet::<$conf>::weight_to_gas(res.gas_consumed);
+                let gas_required = pallet_ethink::Pallet::<$conf>::weight_to_gas(res.gas_required);
+
+                match res.result {
+                    Ok(ret) => Ok(pallet_ethink::ExecResult {
+                        reverted: ret.did_revert(),
+                        output: ret.data,
+                        gas_used,
+                        gas_required,
+                        logs: pallet_ethink::contracts_logs::<$conf>(events),
+                        storage_deposit: pallet_ethink::contracts_storage_deposit::<$conf>(
+                            res.storage_deposit,
+                        ),
+                    }),
+                    Err(error) => Err(pallet_ethink::ExecError { error, gas_used }),
+                }
+            }
+
+            fn instantiate(
+                deployer: &<$conf as frame_system::Config>::AccountId,
+                value: pallet_ethink::BalanceOf<$conf>,
+                input: Vec<u8>,
+                salt: Vec<u8>,
+                gas_limit: sp_core::U256,
+                storage_deposit_limit: Option<pallet_ethink::BalanceOf<$conf>>,
+            ) -> Result<
+                (
+                    <$conf as frame_system::Config>::AccountId,
+                    pallet_ethink::ExecResult,
+                ),
+                pallet_ethink::ExecError,
+            > {
+                // Input which is not a wasm blob can't be deployed, all the gas is spent on it
+                let Some((code, data)) = pallet_ethink::split_wasm_input(&input) else {
+                    return Err(pallet_ethink::ExecError {
+                        error: pallet_contracts::Error::<$conf>::CodeRejected.into(),
+                        gas_used: gas_limit,
+                    });
+                };
+                let events = pallet_ethink::EventsCursor::current::<$conf>();
+                let res = Self::bare_instantiate(
+                    deployer.clone(),
+                    value,
+                    pallet_ethink::Pallet::<$conf>::gas_to_weight(gas_limit),
+                    storage_deposit_limit,
+                    pallet_contracts::Code::Upload(code),
+                    data,
+                    salt,
+                    pallet_contracts::DebugInfo::Skip,
+                    pallet_contracts::CollectEvents::Skip,
+                );
+                let gas_used = pallet_ethink::Pallet::<$conf>::weight_to_gas(res.gas_consumed);
+                let gas_required = pallet_ethink::Pallet::<$conf>::weight_to_gas(res.gas_required);
+
+                match res.result {
+                    Ok(ret) => Ok((
+                        ret.account_id,
+                        pallet_ethink::ExecResult {
+                            reverted: ret.result.did_revert(),
+                            output: ret.result.data,
+                            gas_used,
+                            gas_required,
+                            logs: pallet_ethink::contracts_logs::<$conf>(events),
+                            storage_deposit: pallet_ethink::contracts_storage_deposit::<$conf>(
+                                res.storage_deposit,
+                            ),
+                        },
+                    )),
+                    Err(error) => Err(pallet_ethink::ExecError { error, gas_used }),
+                }
             }
         }
     };
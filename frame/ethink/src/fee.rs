@@ -0,0 +1,84 @@
+use super::*;
+use frame_support::traits::{
+    fungible::{Balanced, Credit, Debt},
+    tokens::Precision,
+    Imbalance, OnUnbalanced,
+};
+use sp_runtime::traits::{Saturating, Zero};
+
+/// Handle withdrawing, refunding and depositing of Ethereum transaction fees.
+pub trait OnChargeEthTransaction<T: pallet::Config> {
+    /// Information on the fee withdrawn, passed on to the correction step.
+    type LiquidityInfo: Default;
+
+    /// Withdraw the maximum fee the transaction could cost, before it gets executed.
+    fn withdraw_fee(
+        who: &T::AccountId,
+        fee: BalanceOf<T>,
+    ) -> Result<Self::LiquidityInfo, DispatchError>;
+
+    /// Refund the difference between the fee withdrawn and the fee `corrected_fee`
+    /// actually due upon transaction execution.
+    fn correct_and_deposit_fee(
+        who: &T::AccountId,
+        corrected_fee: BalanceOf<T>,
+        already_withdrawn: Self::LiquidityInfo,
+    ) -> Result<(), DispatchError>;
+}
+
+/// Default fee handler which withdraws fees from the payer's account by means of a `fungible`,
+/// and hands them over to `OU`, like `pallet_transaction_payment::FungibleAdapter` does.
+pub struct FungibleAdapter<F, OU>(PhantomData<(F, OU)>);
+
+impl<T, F, OU> OnChargeEthTransaction<T> for FungibleAdapter<F, OU>
+where
+    T: pallet::Config,
+    F: Balanced<T::AccountId, Balance = BalanceOf<T>>,
+    OU: OnUnbalanced<Credit<T::AccountId, F>>,
+{
+    type LiquidityInfo = Option<Credit<T::AccountId, F>>;
+
+    fn withdraw_fee(
+        who: &T::AccountId,
+        fee: BalanceOf<T>,
+    ) -> Result<Self::LiquidityInfo, DispatchError> {
+        if fee.is_zero() {
+            return Ok(None);
+        }
+        // Fee can't reap the account, though the transaction value can
+        F::withdraw(
+            who,
+            fee,
+            Precision::Exact,
+            Preservation::Preserve,
+            Fortitude::Polite,
+        )
+        .map(Some)
+    }
+
+    fn correct_and_deposit_fee(
+        who: &T::AccountId,
+        corrected_fee: BalanceOf<T>,
+        already_withdrawn: Self::LiquidityInfo,
+    ) -> Result<(), DispatchError> {
+        let Some(paid) = already_withdrawn else {
+            return Ok(());
+        };
+        let refund = paid.peek().saturating_sub(corrected_fee);
+        // Refund to the payer only if its account still exists, e.g. it was not reaped
+        // by sending all its balance away, and only as much as it can take
+        let refund_imbalance = if F::total_balance(who) > Zero::zero() {
+            F::deposit(who, refund, Precision::BestEffort)
+                .unwrap_or_else(|_| Debt::<T::AccountId, F>::zero())
+        } else {
+            Debt::<T::AccountId, F>::zero()
+        };
+        let fee = paid
+            .offset(refund_imbalance)
+            .same()
+            .map_err(|_| DispatchError::Other("Fee refund exceeds the fee withdrawn"))?;
+        OU::on_unbalanced(fee);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use super::*;
+
+/// Conversion between Ethereum gas and two-dimensional weight.
+pub trait GasWeightMapping {
+    /// Weight the given amount of gas allows to spend
+    fn gas_to_weight(gas: u64) -> Weight;
+    /// Gas needed to spend the given weight, in both of its dimensions
+    fn weight_to_gas(weight: Weight) -> u64;
+    /// Maximum gas which can be spent within the given weight
+    fn gas_limit_for(weight: Weight) -> u64;
+}
+
+/// Mapping with fixed ratios: a unit of gas buys `WeightPerGas` of ref_time,
+/// and `GasPerProofByte` units of gas buy a byte of proof size.
+///
+/// `GasPerProofByte` should be non-zero, and it bounds the PoV size of Ethereum transactions:
+/// a transaction with the gas limit `G` can't add more than `G / GasPerProofByte` bytes to it.
+pub struct FixedGasWeightMapping<WeightPerGas, GasPerProofByte>(
+    PhantomData<(WeightPerGas, GasPerProofByte)>,
+);
+
+impl<W: Get<u64>, R: Get<u64>> GasWeightMapping for FixedGasWeightMapping<W, R> {
+    fn gas_to_weight(gas: u64) -> Weight {
+        Weight::from_parts(gas.saturating_mul(W::get()), gas / R::get().max(1))
+    }
+
+    fn weight_to_gas(weight: Weight) -> u64 {
+        let for_ref_time = weight.ref_time().div_ceil(W::get().max(1));
+        let for_proof_size = weight.proof_size().saturating_mul(R::get().max(1));
+
+        for_ref_time.max(for_proof_size)
+    }
+
+    fn gas_limit_for(weight: Weight) -> u64 {
+        let by_ref_time = weight.ref_time() / W::get().max(1);
+        let by_proof_size = weight.proof_size().saturating_mul(R::get().max(1));
+
+        by_ref_time.min(by_proof_size)
+    }
+}
@@ -26,28 +26,49 @@
 // `no_std` when compiling to WebAssembly
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::comparison_chain, clippy::large_enum_variant)]
+use ep_eth::{
+    ordered_trie_root, Bloom, EIP658ReceiptData, EnvelopedEncodable, Log, PartialHeader, H64,
+};
 use frame_support::{
-    dispatch::{DispatchInfo, PostDispatchInfo},
-    traits::fungible::{Inspect, Mutate},
+    dispatch::{DispatchClass, DispatchInfo, DispatchResultWithPostInfo, Pays, PostDispatchInfo},
+    storage::{with_transaction, TransactionOutcome},
+    traits::{
+        fungible::{Inspect, Mutate},
+        tokens::{Fortitude, Preservation},
+        Get,
+    },
     weights::Weight,
 };
-use frame_system::{pallet_prelude::OriginFor, CheckWeight, Pallet as System};
+use frame_system::{
+    pallet_prelude::{BlockNumberFor, OriginFor},
+    CheckWeight, Pallet as System,
+};
 use scale_codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
 use sp_core::{H160, H256, U256};
 use sp_runtime::{
-    traits::{Block as BlockT, DispatchInfoOf, Dispatchable},
+    traits::{
+        Block as BlockT, Bounded, CheckedSub, DispatchInfoOf, Dispatchable, One,
+        SaturatedConversion, Saturating, UniqueSaturatedInto, Zero,
+    },
     transaction_validity::{
-        InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransactionBuilder,
+        InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError,
+        ValidTransactionBuilder,
     },
     DispatchError, RuntimeDebug,
 };
 use sp_std::vec::Vec;
 use sp_std::{marker::PhantomData, prelude::*};
 
+mod balance;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+mod deposit;
 mod exec;
+mod fee;
+mod gas;
+mod logs;
+mod overrides;
 
 pub mod weights;
 
@@ -57,14 +78,45 @@ mod mock;
 mod tests;
 
 pub use self::{pallet::*, weights::WeightInfo};
-pub use ep_eth::{EthTransaction, LegacyTransactionMessage, Receipt, TransactionAction};
-pub use exec::Executor;
+pub use balance::{BalanceConverter, DecimalsConverter};
+pub use deposit::{ConstDepositLimit, DepositLimit, FeeBudgetDepositLimit};
+pub use ep_eth::{
+    Block as EthBlock, EIP1559TransactionMessage, EIP2930TransactionMessage, EthTransaction,
+    LegacyTransactionMessage, Receipt, StorageDeposit, TransactionAction, TransactionStatus,
+};
+pub use exec::{
+    contracts_clear_storage, contracts_logs, contracts_pristine_code, contracts_set_storage,
+    contracts_storage_deposit, split_wasm_input, DryRunError, EventsCursor, ExecError, ExecResult,
+    Executor,
+};
+pub use fee::{FungibleAdapter, OnChargeEthTransaction};
+pub use gas::{FixedGasWeightMapping, GasWeightMapping};
+pub use logs::{logs_bloom, LogMapping, RawLogs};
+pub use overrides::StateOverride;
 
 pub type BalanceOf<T> =
     <<T as Config>::Currency as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
 
 pub const ETH_BASE_GAS_FEE: u64 = 21_000;
 
+/// Reasons for Ethereum transaction to be rejected,
+/// reported as `InvalidTransaction::Custom` codes.
+#[repr(u8)]
+pub enum TransactionValidationError {
+    /// EIP-155 chain id of the transaction does not match the one of this chain
+    InvalidChainId = 0,
+    /// Value or gas price of the transaction has dust unrepresentable in native balance
+    UnrepresentableAmount = 1,
+    /// Legacy transaction carries no EIP-155 chain id, while unprotected ones are not allowed
+    UnprotectedTransaction = 2,
+}
+
+impl From<TransactionValidationError> for TransactionValidityError {
+    fn from(e: TransactionValidationError) -> Self {
+        TransactionValidityError::Invalid(InvalidTransaction::Custom(e as u8))
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode, MaxEncodedLen, TypeInfo)]
 pub enum RawOrigin {
     EthTransaction(H160),
@@ -93,7 +145,6 @@ where
     T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
     T::AccountId: From<sp_core::H160> + Into<sp_core::H160> + AsRef<[u8]>,
     T::Contracts: Executor<T>,
-    BalanceOf<T>: TryFrom<sp_core::U256>,
 {
     pub fn is_self_contained(&self) -> bool {
         matches!(self, Call::transact { .. })
@@ -101,7 +152,11 @@ where
 
     pub fn check_self_contained(&self) -> Option<Result<H160, TransactionValidityError>> {
         match self {
-            Call::transact { tx } => Some(Pallet::<T>::check_eth_signature(tx)),
+            Call::transact { tx } => Some(
+                Pallet::<T>::check_chain_id(tx)
+                    .and_then(|_| Pallet::<T>::check_amounts(tx))
+                    .and_then(|_| Pallet::<T>::check_eth_signature(tx)),
+            ),
             // Not a self-contained call
             _ => None,
         }
@@ -109,11 +164,36 @@ where
 
     pub fn pre_dispatch_self_contained(
         &self,
-        _origin: &H160,
-        _dispatch_info: &DispatchInfoOf<T::RuntimeCall>,
-        _len: usize,
+        origin: &H160,
+        dispatch_info: &DispatchInfoOf<T::RuntimeCall>,
+        len: usize,
     ) -> Option<Result<(), TransactionValidityError>> {
-        Some(Ok(()))
+        match self {
+            Call::transact { tx } => {
+                if let Err(e) = CheckWeight::<T>::do_pre_dispatch(dispatch_info, len) {
+                    return Some(Err(e));
+                }
+                if let Err(e) = Pallet::<T>::check_gas(tx) {
+                    return Some(Err(e));
+                }
+                // Upon dispatch, transaction nonce must be exactly the next one for the sender
+                let account_nonce = Pallet::<T>::account_nonce(origin);
+                let tx_nonce = Pallet::<T>::tx_nonce(tx);
+                if tx_nonce < account_nonce {
+                    return Some(Err(InvalidTransaction::Stale.into()));
+                }
+                if tx_nonce > account_nonce {
+                    return Some(Err(InvalidTransaction::Future.into()));
+                }
+                // Balance could have been spent since the transaction got into the pool
+                if let Err(e) = Pallet::<T>::check_balance(origin, tx) {
+                    return Some(Err(e));
+                }
+
+                Some(Ok(()))
+            }
+            _ => None,
+        }
     }
 
     pub fn validate_self_contained(
@@ -127,12 +207,29 @@ where
                 if let Err(e) = CheckWeight::<T>::do_validate(dispatch_info, len) {
                     return Some(Err(e));
                 }
-                let tx_nonce = match tx {
-                    EthTransaction::Legacy(t) => t.nonce,
-                    EthTransaction::EIP2930(t) => t.nonce,
-                    EthTransaction::EIP1559(t) => t.nonce,
-                };
-                let builder = ValidTransactionBuilder::default().and_provides((origin, tx_nonce));
+                // Transaction should pay at least the minimum gas price for the base gas
+                if let Err(e) = Pallet::<T>::check_gas(tx) {
+                    return Some(Err(e));
+                }
+                let account_nonce = Pallet::<T>::account_nonce(origin);
+                let tx_nonce = Pallet::<T>::tx_nonce(tx);
+                if tx_nonce < account_nonce {
+                    return Some(Err(InvalidTransaction::Stale.into()));
+                }
+                // Sender should be able to afford both value and the maximum fee
+                if let Err(e) = Pallet::<T>::check_balance(origin, tx) {
+                    return Some(Err(e));
+                }
+                // Transaction with the same sender and nonce, but higher gas price,
+                // replaces the one in the pool, as it provides the same tag with higher priority
+                let mut builder = ValidTransactionBuilder::default()
+                    .and_provides((origin, tx_nonce))
+                    .priority(Pallet::<T>::tx_priority(tx));
+                // Transaction with a future nonce waits in the pool
+                // until the one with the previous nonce gets there
+                if tx_nonce > account_nonce {
+                    builder = builder.and_requires((origin, tx_nonce - 1));
+                }
 
                 Some(builder.build())
             }
@@ -163,6 +260,27 @@ pub mod pallet {
         type Currency: Inspect<Self::AccountId> + Mutate<Self::AccountId>;
         /// Contracts engine
         type Contracts: Executor<Self>;
+        /// Handler for withdrawing and refunding Ethereum transaction fees
+        type OnChargeTransaction: OnChargeEthTransaction<Self>;
+        /// Conversion of contract events into Ethereum logs
+        type LogMapping: LogMapping;
+        /// Conversion between Ethereum gas and weight
+        type GasWeightMapping: GasWeightMapping;
+        /// Conversion between amounts in wei and native balance
+        type BalanceConverter: BalanceConverter<BalanceOf<Self>>;
+        /// Number of the latest blocks locations of Ethereum transactions are kept for.
+        /// Must be non-zero.
+        #[pallet::constant]
+        type TransactionRetention: Get<BlockNumberFor<Self>>;
+        /// Limit on storage deposit of Ethereum transactions
+        type DepositLimit: DepositLimit<BalanceOf<Self>>;
+        /// EIP-155 chain id, used for transactions replay protection
+        #[pallet::constant]
+        type ChainId: Get<u64>;
+        /// Whether to accept legacy transactions signed without EIP-155 chain id,
+        /// which can be replayed on any chain
+        #[pallet::constant]
+        type AllowUnprotectedTxs: Get<bool>;
         /// Weights for extrinsics
         type WeightInfo: WeightInfo;
     }
@@ -174,43 +292,128 @@ pub mod pallet {
         T::AccountId: From<sp_core::H160> + Into<sp_core::H160> + AsRef<[u8]>,
         T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
         T::Contracts: Executor<T>,
-        BalanceOf<T>: TryFrom<sp_core::U256>,
     {
         /// Transact a call coming from Ethereum RPC
         #[pallet::call_index(0)]
-        #[pallet::weight(<T as pallet::Config>::WeightInfo::transact())]
-        pub fn transact(origin: OriginFor<T>, tx: EthTransaction) -> DispatchResult {
+        #[pallet::weight(Pallet::<T>::transact_weight(tx))]
+        pub fn transact(origin: OriginFor<T>, tx: EthTransaction) -> DispatchResultWithPostInfo {
             let origin: frame_system::RawOrigin<T::AccountId> =
                 ensure_eth_transaction(origin)?.into();
-            // We received Ethereum transaction,
-            // need to route it either as a contract call or just a balance transfer
-            let from = origin.clone();
-            let from = from.as_signed().ok_or(Error::<T>::BadEthSignature)?;
-            let (to, value, data, gas_limit) =
-                Self::unpack_eth_tx(&tx).ok_or(Error::<T>::TxNotSupported)?;
-            // CREATE is not supported
-            let to = to.ok_or(Error::<T>::TxNotSupported)?;
+            let from = origin.as_signed().ok_or(Error::<T>::BadEthSignature)?;
+            let (to, value, data, gas_limit) = Self::unpack_eth_tx(&tx);
+            let value = Self::wei_to_native(value)?;
+            // Sender nonce is used as a salt, so that same code deployed by
+            // the same account yields a different address every time, like in Ethereum
+            let salt = System::<T>::account_nonce(from).encode();
             // Increment nonce of the sender account
             System::<T>::inc_account_nonce(from);
-            // Compose proper destination pallet call
-            let call = T::Contracts::build_call(to.clone(), value, data.clone(), gas_limit)
-                .ok_or(Error::<T>::TxNotSupported)?;
-            // Make call
-            log::debug!(target: "ethink:pallet", "Dispatching CALL {:?}\n DATA in hex: {}", &call, hex::encode(&data));
-            let _ = call.dispatch(origin.into()).map_err(|e| {
-                log::error!(target: "ethink:pallet", "Failed: {:?}", &e);
-                Error::<T>::TxExecutionFailed
+            // Withdraw the maximum fee the transaction could cost
+            let gas_price = Self::tx_gas_price(&tx);
+            let max_fee = Self::fee(gas_limit, gas_price)?;
+            let withdrawn = T::OnChargeTransaction::withdraw_fee(from, max_fee).map_err(|e| {
+                log::error!(target: "ethink:pallet", "Can't withdraw fee: {:?}", &e);
+                Error::<T>::InsufficientBalanceForFee
             })?;
-            // Deposit Event
+            // Ethereum transactions set no limit on storage deposit, so it comes from
+            // the `DepositLimit` policy, which is given the fee budget to derive it from
+            let deposit_limit = T::DepositLimit::deposit_limit(max_fee);
+            // We received Ethereum transaction, need to route it either as
+            // a contract call, a balance transfer, or CREATE of a new contract
+            log::debug!(target: "ethink:pallet", "Executing tx to {:?}\n DATA in hex: {}", &to, hex::encode(&data));
+            let mut created = None;
+            let result = match &to {
+                Some(to) if T::Contracts::is_contract(to) => {
+                    T::Contracts::call(from, to.clone(), value, data, gas_limit, deposit_limit)
+                }
+                Some(to) => Self::transfer(from, to, value),
+                None => {
+                    T::Contracts::instantiate(from, value, data, salt, gas_limit, deposit_limit)
+                        .map(|(address, result)| {
+                            created = Some(address);
+                            result
+                        })
+                }
+            };
+            // Failed transaction still gets into the block, keeping the nonce bumped
+            // and the fee charged, like in Ethereum
+            let (result, failure) = match result {
+                Ok(r) if r.reverted => {
+                    let failure = (Error::<T>::TxReverted.into(), r.output);
+                    let result = ExecResult {
+                        gas_used: r.gas_used,
+                        storage_deposit: r.storage_deposit,
+                        ..Default::default()
+                    };
+                    (result, Some(failure))
+                }
+                Ok(r) => (r, None),
+                Err(e) => {
+                    log::debug!(target: "ethink:pallet", "Failed: {:?}", &e.error);
+                    let result = ExecResult {
+                        gas_used: e.gas_used,
+                        ..Default::default()
+                    };
+                    (result, Some((e.error, Vec::new())))
+                }
+            };
+            let succeeded = failure.is_none();
+            // Refund fee for the gas left unused
+            let gas_used = result.gas_used.min(gas_limit);
+            T::OnChargeTransaction::correct_and_deposit_fee(
+                from,
+                Self::fee(gas_used, gas_price)?,
+                withdrawn,
+            )?;
+            // Record transaction and deposit Event
             let tx_hash = tx.hash();
-            let from = (*from).clone().into();
-            Self::deposit_event(Event::TxExecuted {
+            let from = from.clone().into();
+            let to = to.map(Into::into);
+            let storage_deposit = result.storage_deposit;
+            let status = TransactionStatus {
+                transaction_hash: tx_hash,
                 from,
-                to: to.into(),
-                tx_hash,
-            });
+                to,
+                contract_address: created.clone().filter(|_| succeeded).map(Into::into),
+                logs: result.logs,
+                revert_data: failure.as_ref().map(|f| f.1.clone()).unwrap_or_default(),
+                storage_deposit,
+                ..Default::default()
+            };
+            let transaction_index = Self::record_transaction(tx, status, gas_used, succeeded);
+            Self::index_transaction(tx_hash, transaction_index);
+            match (to, created, failure) {
+                (_, _, Some((reason, revert_data))) => Self::deposit_event(Event::TxFailed {
+                    from,
+                    to,
+                    tx_hash,
+                    transaction_index,
+                    gas_used,
+                    reason,
+                    revert_data,
+                }),
+                (Some(to), _, None) => Self::deposit_event(Event::TxExecuted {
+                    from,
+                    to,
+                    tx_hash,
+                    transaction_index,
+                    gas_used,
+                    storage_deposit,
+                }),
+                (None, Some(address), None) => Self::deposit_event(Event::ContractCreated {
+                    from,
+                    address: address.into(),
+                    tx_hash,
+                    transaction_index,
+                    gas_used,
+                    storage_deposit,
+                }),
+                _ => (),
+            }
 
-            Ok(())
+            Ok(PostDispatchInfo {
+                actual_weight: Some(Self::transact_weight_for(gas_used)),
+                pays_fee: Pays::Yes,
+            })
         }
     }
 
@@ -218,22 +421,281 @@ pub mod pallet {
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event {
         /// A call coming from ETH RPC was successfully executed.
-        TxExecuted { from: H160, to: H160, tx_hash: H256 },
+        TxExecuted {
+            from: H160,
+            to: H160,
+            tx_hash: H256,
+            transaction_index: u32,
+            gas_used: U256,
+            storage_deposit: StorageDeposit,
+        },
+        /// A contract was instantiated by a transaction coming from ETH RPC.
+        ContractCreated {
+            from: H160,
+            address: H160,
+            tx_hash: H256,
+            transaction_index: u32,
+            gas_used: U256,
+            storage_deposit: StorageDeposit,
+        },
+        /// A transaction coming from ETH RPC failed or was reverted by the contract.
+        /// It is still included into the block, with the gas used charged.
+        TxFailed {
+            from: H160,
+            to: Option<H160>,
+            tx_hash: H256,
+            transaction_index: u32,
+            gas_used: U256,
+            reason: DispatchError,
+            revert_data: Vec<u8>,
+        },
     }
 
     #[pallet::error]
     pub enum Error<T> {
         /// Signature is invalid
         BadEthSignature,
-        /// Type of transaction is not supported
-        TxNotSupported,
-        /// Transaction execution failed
-        TxExecutionFailed,
+        /// Contract reverted the transaction
+        TxReverted,
+        /// Sender can't afford the fee for the gas limit set
+        InsufficientBalanceForFee,
+        /// Transaction fee overflows balance type
+        FeeOverflow,
+        /// Amount in wei has dust unrepresentable in native balance, or overflows it
+        UnrepresentableAmount,
     }
 
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+            Pending::<T>::kill();
+            CurrentBlock::<T>::kill();
+            CurrentReceipts::<T>::kill();
+            CurrentTransactionStatuses::<T>::kill();
+            // Account for the writes made upon block finalization as well
+            T::DbWeight::get()
+                .writes(4 + 3)
+                .saturating_add(Self::prune_transaction_locations(n))
+        }
+
+        fn on_finalize(_n: BlockNumberFor<T>) {
+            Self::store_block();
+        }
+
+        fn integrity_test() {
+            assert!(
+                !T::TransactionRetention::get().is_zero(),
+                "TransactionRetention must be non-zero"
+            );
+        }
+    }
+
+    /// Ethereum transactions executed in the current block so far,
+    /// along with their statuses and receipts.
+    #[pallet::storage]
+    pub type Pending<T: Config> =
+        StorageValue<_, Vec<(EthTransaction, TransactionStatus, Receipt)>, ValueQuery>;
+
+    /// The current Ethereum block.
+    #[pallet::storage]
+    pub type CurrentBlock<T: Config> = StorageValue<_, EthBlock>;
+
     /// The current Ethereum receipts.
     #[pallet::storage]
     pub type CurrentReceipts<T: Config> = StorageValue<_, Vec<Receipt>>;
+
+    /// The current Ethereum transaction statuses.
+    #[pallet::storage]
+    pub type CurrentTransactionStatuses<T: Config> = StorageValue<_, Vec<TransactionStatus>>;
+
+    /// Locations of the Ethereum transactions executed within the retention window, by hash:
+    /// block number, extrinsic index and index among the Ethereum transactions of the block.
+    #[pallet::storage]
+    pub type TransactionLocations<T: Config> =
+        StorageMap<_, Twox64Concat, H256, (BlockNumberFor<T>, u32, u32)>;
+
+    /// Hashes of the Ethereum transactions executed in the block,
+    /// for their locations to be pruned once the block leaves the retention window.
+    #[pallet::storage]
+    pub type BlockTransactionHashes<T: Config> =
+        StorageMap<_, Twox64Concat, BlockNumberFor<T>, Vec<H256>, ValueQuery>;
+}
+
+impl<T: Config> Pallet<T> {
+    /// Weight of the `transact` call declared upfront:
+    /// the base weight plus the weight of all the gas the transaction is allowed to spend.
+    pub fn transact_weight(tx: &EthTransaction) -> Weight {
+        Self::transact_weight_for(Self::tx_gas_limit(tx))
+    }
+
+    /// Weight of the `transact` call spending the given amount of gas.
+    fn transact_weight_for(gas: U256) -> Weight {
+        <T as Config>::WeightInfo::transact().saturating_add(Self::gas_to_weight(gas))
+    }
+
+    /// Weight the given amount of gas allows to spend.
+    pub fn gas_to_weight(gas: U256) -> Weight {
+        T::GasWeightMapping::gas_to_weight(gas.min(U256::from(u64::MAX)).as_u64())
+    }
+
+    /// Gas needed to spend the given weight.
+    pub fn weight_to_gas(weight: Weight) -> U256 {
+        T::GasWeightMapping::weight_to_gas(weight).into()
+    }
+
+    /// Maximum gas a single Ethereum transaction can spend,
+    /// so that it still fits into the block.
+    pub fn block_gas_limit() -> U256 {
+        let weights = T::BlockWeights::get();
+        let max_weight = weights
+            .get(DispatchClass::Normal)
+            .max_extrinsic
+            .unwrap_or(weights.max_block)
+            .saturating_sub(<T as Config>::WeightInfo::transact());
+
+        T::GasWeightMapping::gas_limit_for(max_weight).into()
+    }
+
+    fn tx_gas_limit(tx: &EthTransaction) -> U256 {
+        match tx {
+            EthTransaction::Legacy(t) => t.gas_limit,
+            EthTransaction::EIP2930(t) => t.gas_limit,
+            EthTransaction::EIP1559(t) => t.gas_limit,
+        }
+    }
+
+    /// Put executed transaction to the pending ones of the current block.
+    /// Returns index of the transaction among the Ethereum transactions of the block.
+    fn record_transaction(
+        tx: EthTransaction,
+        mut status: TransactionStatus,
+        gas_used: U256,
+        succeeded: bool,
+    ) -> u32 {
+        Pending::<T>::mutate(|pending| {
+            let cumulative_gas_used = pending
+                .last()
+                .map(|(_, _, receipt)| Self::receipt_data(receipt).used_gas)
+                .unwrap_or_default()
+                .saturating_add(gas_used);
+            status.transaction_index = pending.len() as u32;
+            status.logs_bloom = logs_bloom(&status.logs);
+            let data = EIP658ReceiptData {
+                status_code: succeeded.into(),
+                used_gas: cumulative_gas_used,
+                logs_bloom: status.logs_bloom,
+                logs: status.logs.clone(),
+            };
+            let receipt = match tx {
+                EthTransaction::Legacy(_) => Receipt::Legacy(data),
+                EthTransaction::EIP2930(_) => Receipt::EIP2930(data),
+                EthTransaction::EIP1559(_) => Receipt::EIP1559(data),
+            };
+            pending.push((tx, status, receipt));
+            pending.len() as u32 - 1
+        })
+    }
+
+    /// Remember location of the transaction executed in the current block.
+    fn index_transaction(tx_hash: H256, transaction_index: u32) {
+        let number = System::<T>::block_number();
+        let extrinsic_index = System::<T>::extrinsic_index().unwrap_or_default();
+        TransactionLocations::<T>::insert(tx_hash, (number, extrinsic_index, transaction_index));
+        BlockTransactionHashes::<T>::append(number, tx_hash);
+    }
+
+    /// Forget locations of the transactions of the block leaving the retention window
+    /// upon the start of block `n`.
+    fn prune_transaction_locations(n: BlockNumberFor<T>) -> Weight {
+        let Some(expired) = n.checked_sub(&T::TransactionRetention::get()) else {
+            return Weight::zero();
+        };
+        let hashes = BlockTransactionHashes::<T>::take(expired);
+        hashes.iter().for_each(TransactionLocations::<T>::remove);
+        T::DbWeight::get().reads_writes(1, 1 + hashes.len() as u64)
+    }
+
+    /// Where the Ethereum transaction with the given hash was executed,
+    /// provided it is within the retention window.
+    pub fn transaction_location(hash: H256) -> Option<TransactionLocation> {
+        TransactionLocations::<T>::get(hash).map(|(number, extrinsic_index, transaction_index)| {
+            let number: u128 = number.unique_saturated_into();
+            TransactionLocation {
+                block_number: number.into(),
+                extrinsic_index,
+                transaction_index,
+            }
+        })
+    }
+
+    /// Plain balance transfer to an account which is not a contract.
+    /// It costs the standard base fee, whether it passes or not.
+    fn transfer(
+        from: &T::AccountId,
+        to: &T::AccountId,
+        value: BalanceOf<T>,
+    ) -> Result<ExecResult, ExecError> {
+        let gas_used = U256::from(ETH_BASE_GAS_FEE);
+        T::Currency::transfer(from, to, value, Preservation::Expendable)
+            .map(|_| ExecResult {
+                gas_used,
+                gas_required: gas_used,
+                ..Default::default()
+            })
+            .map_err(|error| ExecError { error, gas_used })
+    }
+
+    fn to_h256(hash: &T::Hash) -> H256 {
+        H256::decode(&mut hash.as_ref()).unwrap_or_default()
+    }
+
+    fn receipt_data(receipt: &Receipt) -> &EIP658ReceiptData {
+        match receipt {
+            Receipt::Legacy(d) | Receipt::EIP2930(d) | Receipt::EIP1559(d) => d,
+        }
+    }
+
+    /// Build Ethereum block out of the transactions executed in the current block.
+    fn store_block() {
+        let mut transactions = Vec::new();
+        let mut statuses = Vec::new();
+        let mut receipts = Vec::new();
+        let mut logs_bloom = Bloom::default();
+        for (tx, status, receipt) in Pending::<T>::take() {
+            logs_bloom.accrue_bloom(&status.logs_bloom);
+            transactions.push(tx);
+            statuses.push(status);
+            receipts.push(receipt);
+        }
+        let gas_used = receipts
+            .last()
+            .map(|r| Self::receipt_data(r).used_gas)
+            .unwrap_or_default();
+        let number: u128 = System::<T>::block_number().unique_saturated_into();
+        let parent_hash = Self::to_h256(&System::<T>::parent_hash());
+
+        let partial_header = PartialHeader {
+            parent_hash,
+            beneficiary: H160::zero(),
+            // State root is not known until the block is finalized
+            state_root: H256::zero(),
+            receipts_root: ordered_trie_root(receipts.iter().map(EnvelopedEncodable::encode)),
+            logs_bloom,
+            difficulty: U256::zero(),
+            number: number.into(),
+            gas_limit: Self::block_gas_limit(),
+            gas_used,
+            timestamp: 0,
+            extra_data: Vec::new(),
+            mix_hash: H256::zero(),
+            nonce: H64::zero(),
+        };
+        let block = EthBlock::new(partial_header, transactions, Vec::new());
+
+        CurrentBlock::<T>::put(block);
+        CurrentReceipts::<T>::put(receipts);
+        CurrentTransactionStatuses::<T>::put(statuses);
+    }
 }
 
 impl<T> Pallet<T>
@@ -246,67 +708,296 @@ where
         T::Contracts::code_at(&address)
     }
 
+    pub fn code_hash_at(address: T::AccountId) -> Option<H256> {
+        T::Contracts::code_hash_at(&address)
+    }
+
+    /// Value stored by the contract at the EVM storage slot given,
+    /// see [`Self::storage_key`] for how the slot is mapped to the contract storage.
+    pub fn storage_at(address: T::AccountId, slot: U256) -> Option<Vec<u8>> {
+        T::Contracts::storage_at(&address, Self::storage_key(slot))
+    }
+
+    /// Contract storage key for the EVM storage slot given.
+    ///
+    /// Slots which fit into `u32` are ink! storage keys, hence they are SCALE-encoded as `u32`.
+    /// This way slot `0` is the root storage of an ink! contract, and any other such slot
+    /// is a `Lazy` field with that storage key.
+    /// Larger slots are passed through as a raw 32-byte big-endian key.
+    ///
+    /// Entries of an ink! `Mapping` are stored under its storage key followed by the
+    /// SCALE-encoded entry key, so they are not addressable by slot in general,
+    /// while the slot of the mapping storage key itself holds nothing.
+    pub fn storage_key(slot: U256) -> Vec<u8> {
+        if slot <= U256::from(u32::MAX) {
+            return slot.low_u32().encode();
+        }
+        let mut key = [0u8; 32];
+        slot.to_big_endian(&mut key);
+        key.to_vec()
+    }
+
     pub fn contract_call(
         from: T::AccountId,
         to: T::AccountId,
         data: Vec<u8>,
         value: BalanceOf<T>,
-        gas_limit: Weight,
-    ) -> <T::Contracts as Executor<T>>::ExecResult {
+        gas_limit: U256,
+    ) -> Result<ExecResult, ExecError> {
         log::error!(target: "ethink:pallet", "Contract: {:?} call with input: {}", hex::encode(&to), hex::encode(&data));
-        T::Contracts::call(from, to, data, value, gas_limit)
+        T::Contracts::call(&from, to, value, data, gas_limit, None)
     }
 
+    /// Estimate gas the call needs to pass, capped by both `gas_limit` and the block gas limit.
+    /// Without the destination given, estimate gas to instantiate contract from `data` (CREATE).
+    ///
+    /// Gas the contract reports as required is just the first guess, as it is not always
+    /// the lowest gas limit the call passes with. So we binary-search for the latter
+    /// between the gas used and the gas required, or the cap if the call fails with the former.
     pub fn gas_estimate(
         from: T::AccountId,
-        to: T::AccountId,
+        to: Option<T::AccountId>,
         data: Vec<u8>,
         value: BalanceOf<T>,
-        gas_limit: Weight,
-    ) -> Result<U256, DispatchError> {
-        T::Contracts::gas_estimate(from, to, data, value, gas_limit)
+        gas_limit: U256,
+    ) -> Result<U256, DryRunError> {
+        let cap = gas_limit.min(Self::block_gas_limit());
+        if let Some(to) = to.as_ref().filter(|to| !T::Contracts::is_contract(to)) {
+            // Standard base fee, provided the transfer passes
+            return Self::dry_run(|| Self::transfer(&from, to, value))
+                .map(|_| U256::from(ETH_BASE_GAS_FEE));
+        }
+        // Salt is the same the transaction would have
+        let salt = System::<T>::account_nonce(&from).encode();
+        let attempt = |gas_limit| {
+            // Storage deposit is limited the same way as for the transaction. Its gas price
+            // is unknown, so the fee budget is taken at the minimum one, for the estimate
+            // to hold at any price the transaction could have
+            let deposit_limit = Self::fee(gas_limit, Self::min_gas_price())
+                .map(T::DepositLimit::deposit_limit)
+                .map_err(DispatchError::from)?;
+            Self::dry_run(|| match &to {
+                Some(to) => T::Contracts::call(
+                    &from,
+                    to.clone(),
+                    value,
+                    data.clone(),
+                    gas_limit,
+                    deposit_limit,
+                ),
+                None => T::Contracts::instantiate(
+                    &from,
+                    value,
+                    data.clone(),
+                    salt.clone(),
+                    gas_limit,
+                    deposit_limit,
+                )
+                .map(|(_, result)| result),
+            })
+        };
+        // If the call fails with the maximum gas allowed, it fails with any other limit as well
+        let result = attempt(cap)?;
+        let mut hi = cap;
+        if result.gas_required < hi && attempt(result.gas_required).is_ok() {
+            hi = result.gas_required;
+        }
+        // The call can't pass with less gas than it has spent
+        let mut lo = result.gas_used.min(hi).saturating_sub(U256::one());
+        while hi - lo > U256::one() {
+            let mid = (lo + hi) / 2;
+            if attempt(mid).is_ok() {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+
+        Ok(hi)
     }
 
-    fn check_eth_signature(tx: &EthTransaction) -> Result<H160, TransactionValidityError> {
-        let mut sig = [0u8; 65];
-        let mut msg = [0u8; 32];
+    /// Run the execution given, with all its state changes rolled back afterwards.
+    fn dry_run(
+        f: impl FnOnce() -> Result<ExecResult, ExecError>,
+    ) -> Result<ExecResult, DryRunError> {
+        with_transaction(|| {
+            let result = match f() {
+                Ok(r) if r.reverted => Err(DryRunError::Reverted(r.output)),
+                Ok(r) => Ok(r),
+                Err(e) => Err(DryRunError::Failed(e.error)),
+            };
+            TransactionOutcome::Rollback(result)
+        })
+    }
+
+    pub fn chain_id() -> u64 {
+        T::ChainId::get()
+    }
+
+    /// Balance of the account, in wei.
+    pub fn account_balance(address: &H160) -> U256
+    where
+        T::AccountId: From<H160>,
+    {
+        T::BalanceConverter::into_wei(T::Currency::balance(&T::AccountId::from(*address)))
+    }
+
+    /// Native balance for the amount given in wei.
+    pub fn wei_to_native(wei: U256) -> Result<BalanceOf<T>, Error<T>> {
+        T::BalanceConverter::into_native(wei).ok_or(Error::<T>::UnrepresentableAmount)
+    }
+
+    /// Minimum gas price, in wei: a single unit of native balance per gas.
+    pub fn min_gas_price() -> U256 {
+        T::BalanceConverter::into_wei(One::one())
+    }
+
+    /// Nonce of the account, as seen by Ethereum.
+    pub fn account_nonce(address: &H160) -> U256
+    where
+        T::AccountId: From<H160>,
+    {
+        let nonce: u128 =
+            System::<T>::account_nonce(T::AccountId::from(*address)).unique_saturated_into();
+        U256::from(nonce)
+    }
+
+    fn tx_nonce(tx: &EthTransaction) -> U256 {
         match tx {
-            EthTransaction::Legacy(t) => {
-                sig[0..32].copy_from_slice(&t.signature.r()[..]);
-                sig[32..64].copy_from_slice(&t.signature.s()[..]);
-                sig[64] = t.signature.standard_v();
-                msg.copy_from_slice(&LegacyTransactionMessage::from(t.clone()).hash()[..]);
+            EthTransaction::Legacy(t) => t.nonce,
+            EthTransaction::EIP2930(t) => t.nonce,
+            EthTransaction::EIP1559(t) => t.nonce,
+        }
+    }
+
+    /// Price per unit of gas the transaction sender pays.
+    /// As there is no base fee, EIP-1559 transaction pays its priority fee, capped by max fee.
+    pub fn tx_gas_price(tx: &EthTransaction) -> U256 {
+        match tx {
+            EthTransaction::Legacy(t) => t.gas_price,
+            EthTransaction::EIP2930(t) => t.gas_price,
+            EthTransaction::EIP1559(t) => t.max_priority_fee_per_gas.min(t.max_fee_per_gas),
+        }
+    }
+
+    /// Priority of the transaction in the pool.
+    ///
+    /// As there is no base fee, the whole gas price is a tip. It is taken per unit of gas,
+    /// so that a transaction with the same nonce but higher gas price replaces the pending one
+    /// whatever their gas limits are. The price is converted to native balance and scaled
+    /// against native tips the same way as `pallet_transaction_payment` does, i.e. multiplied
+    /// by the amount of gas which would fit into a block.
+    fn tx_priority(tx: &EthTransaction) -> TransactionPriority {
+        let price = T::BalanceConverter::into_native(Self::tx_gas_price(tx))
+            .unwrap_or_else(Bounded::max_value);
+        let max_block_gas = T::GasWeightMapping::gas_limit_for(T::BlockWeights::get().max_block);
+
+        price
+            .saturating_mul(max_block_gas.max(1).saturated_into())
+            .saturated_into()
+    }
+
+    /// Fee for the `gas` spent at `gas_price`, in native balance.
+    fn fee(gas: U256, gas_price: U256) -> Result<BalanceOf<T>, Error<T>> {
+        gas.checked_mul(gas_price)
+            .and_then(T::BalanceConverter::into_native)
+            .ok_or(Error::<T>::FeeOverflow)
+    }
+
+    fn check_gas(tx: &EthTransaction) -> Result<(), TransactionValidityError> {
+        if Self::tx_gas_price(tx) < Self::min_gas_price()
+            || Self::tx_gas_limit(tx) < U256::from(ETH_BASE_GAS_FEE)
+        {
+            return Err(InvalidTransaction::Payment.into());
+        }
+
+        Ok(())
+    }
+
+    fn check_balance(origin: &H160, tx: &EthTransaction) -> Result<(), TransactionValidityError>
+    where
+        T::AccountId: From<H160>,
+    {
+        let (_, value, _, gas_limit) = Self::unpack_eth_tx(tx);
+        let max_fee = gas_limit
+            .checked_mul(Self::tx_gas_price(tx))
+            .ok_or(InvalidTransaction::Payment)?;
+        let total = max_fee
+            .checked_add(value)
+            .ok_or(InvalidTransaction::Payment)?;
+        // Compare in wei, as the balance is always representable there
+        let who = T::AccountId::from(*origin);
+        let balance = |preservation| {
+            T::BalanceConverter::into_wei(T::Currency::reducible_balance(
+                &who,
+                preservation,
+                Fortitude::Polite,
+            ))
+        };
+        // Fee is withdrawn keeping the account alive, while the value could take the rest of it
+        if balance(Preservation::Preserve) < max_fee || balance(Preservation::Expendable) < total {
+            return Err(InvalidTransaction::Payment.into());
+        }
+
+        Ok(())
+    }
+
+    fn check_chain_id(tx: &EthTransaction) -> Result<(), TransactionValidityError> {
+        let chain_id = match tx {
+            // Legacy transactions signed before EIP-155 carry no chain id
+            EthTransaction::Legacy(t) => t.signature.chain_id(),
+            EthTransaction::EIP2930(t) => Some(t.chain_id),
+            EthTransaction::EIP1559(t) => Some(t.chain_id),
+        };
+        match chain_id {
+            Some(id) if id != T::ChainId::get() => {
+                Err(TransactionValidationError::InvalidChainId.into())
             }
-            // We only support Legacy. EIP2930 and EIP1559 are not supported
-            _ => {
-                return Err(TransactionValidityError::Invalid(
-                    InvalidTransaction::BadProof,
-                ))
+            None if !T::AllowUnprotectedTxs::get() => {
+                Err(TransactionValidationError::UnprotectedTransaction.into())
             }
+            _ => Ok(()),
+        }
+    }
+
+    /// Value and gas price should be representable in native balance,
+    /// as they are converted to it upon execution.
+    fn check_amounts(tx: &EthTransaction) -> Result<(), TransactionValidityError>
+    where
+        T::AccountId: From<H160>,
+    {
+        let (_, value, _, _) = Self::unpack_eth_tx(tx);
+        if T::BalanceConverter::into_native(value).is_none()
+            || T::BalanceConverter::into_native(Self::tx_gas_price(tx)).is_none()
+        {
+            return Err(TransactionValidationError::UnrepresentableAmount.into());
         }
+
+        Ok(())
+    }
+
+    fn check_eth_signature(tx: &EthTransaction) -> Result<H160, TransactionValidityError> {
         // We check ethereum signature here, and derive sender account from it.
-        sp_io::crypto::secp256k1_ecdsa_recover(&sig, &msg)
-            .map_err(|_| TransactionValidityError::Invalid(InvalidTransaction::BadProof))
-            .map(|p| H160::from(H256::from(sp_io::hashing::keccak_256(&p))))
+        ep_eth::recover_signer(tx).ok_or(TransactionValidityError::Invalid(
+            InvalidTransaction::BadProof,
+        ))
     }
 
-    fn unpack_eth_tx(tx: &EthTransaction) -> Option<(Option<T::AccountId>, U256, Vec<u8>, U256)>
+    fn unpack_eth_tx(tx: &EthTransaction) -> (Option<T::AccountId>, U256, Vec<u8>, U256)
     where
         <T as frame_system::Config>::AccountId: From<ep_eth::H160>,
     {
-        match tx {
-            EthTransaction::Legacy(t) => Some((
-                match t.action {
-                    TransactionAction::Call(h) => Some(h.into()),
-                    TransactionAction::Create => None,
-                },
-                t.value,
-                t.input.clone(),
-                t.gas_limit,
-            )),
-            // We only support Legacy, EIP2930 and EIP1559 are not supported
-            _ => None,
-        }
+        let (action, value, input, gas_limit) = match tx {
+            EthTransaction::Legacy(t) => (&t.action, t.value, &t.input, t.gas_limit),
+            EthTransaction::EIP2930(t) => (&t.action, t.value, &t.input, t.gas_limit),
+            EthTransaction::EIP1559(t) => (&t.action, t.value, &t.input, t.gas_limit),
+        };
+        let to = match action {
+            TransactionAction::Call(h) => Some((*h).into()),
+            TransactionAction::Create => None,
+        };
+
+        (to, value, input.clone(), gas_limit)
     }
 }
 
@@ -316,38 +1007,112 @@ pub enum ReturnValue {
     Hash(H160),
 }
 
+/// Location of an Ethereum transaction on chain.
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct TransactionLocation {
+    /// Number of the block the transaction was executed in
+    pub block_number: U256,
+    /// Index of the extrinsic of the transaction within the block
+    pub extrinsic_index: u32,
+    /// Index of the transaction among the Ethereum transactions of the block
+    pub transaction_index: u32,
+}
+
 sp_api::decl_runtime_apis! {
     /// Runtime-exposed API necessary for ETH-compatibility layer.
+    ///
+    /// Version 2 takes amounts in wei and gas limits in gas for dry runs,
+    /// and exposes Ethereum blocks emulated by the runtime.
+    #[api_version(2)]
     pub trait EthinkAPI {
-        /// Return contract's code hash
+        /// Return contract's code
         fn code_at(address: H160) -> Option<Vec<u8>>;
 
-        /// Return runtime-defined CHAIN_ID.
+        /// Return contract's code hash
+        fn code_hash_at(address: H160) -> Option<H256>;
+
+        /// Return value stored by the contract at the EVM storage slot given.
+        fn storage_at(address: H160, slot: U256) -> Option<Vec<u8>>;
+
+        /// Return EIP-155 chain id, as configured for the pallet.
         fn chain_id() -> u64;
 
-        /// Return account balance.
+        /// Return account balance, in wei.
         fn account_free_balance(address: H160) -> U256;
 
         /// Return account nonce.
         fn nonce(address: H160) -> U256;
 
+        /// Return maximum gas a transaction can spend.
+        fn block_gas_limit() -> U256;
+
+        /// Return minimum gas price, in wei.
+        fn gas_price() -> U256;
+
         /// Call contract (without extrinsic submission)
+        #[changed_in(2)]
         fn call(
             from: H160,
             to: H160,
             data: Vec<u8>,
             value: u128,
             gas_limit: Weight,
-        ) -> Result<Vec<u8>, sp_runtime::DispatchError>;
+        ) -> Result<Vec<u8>, DispatchError>;
+
+        /// Call contract (without extrinsic submission).
+        /// Returns revert data of the contract if it reverts.
+        fn call(
+            from: H160,
+            to: H160,
+            data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+        ) -> Result<Vec<u8>, DryRunError>;
+
+        /// Call contract (without extrinsic submission) with the state of the accounts
+        /// overridden as given. Overrides are rolled back after the call.
+        fn call_with_overrides(
+            from: H160,
+            to: H160,
+            data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+            overrides: Vec<(H160, StateOverride)>,
+        ) -> Result<Vec<u8>, DryRunError>;
 
         /// Estimate gas needed for a contract call
+        #[changed_in(2)]
         fn gas_estimate(
             from: H160,
             to: H160,
             data: Vec<u8>,
             value: u128,
             gas_limit: Weight,
-        ) -> Result<U256, sp_runtime::DispatchError>;
+        ) -> Result<U256, DispatchError>;
+
+        /// Estimate gas needed for a call to pass, capped by `gas_limit`.
+        /// Without `to`, estimate gas needed to instantiate contract from `data`.
+        /// Returns revert data of the contract if it reverts even with the gas limit given.
+        fn gas_estimate(
+            from: H160,
+            to: Option<H160>,
+            data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+        ) -> Result<U256, DryRunError>;
+
+        /// Return Ethereum block emulated for the current block.
+        fn current_block() -> Option<EthBlock>;
+
+        /// Return receipts of Ethereum transactions executed in the current block.
+        fn current_receipts() -> Option<Vec<Receipt>>;
+
+        /// Return statuses of Ethereum transactions executed in the current block.
+        fn current_transaction_statuses() -> Option<Vec<TransactionStatus>>;
+
+        /// Return where the Ethereum transaction with the given hash was executed,
+        /// unless it is older than the retention window.
+        fn transaction_location(hash: H256) -> Option<TransactionLocation>;
 
         /// Wrap Ethereum transaction into an extrinsic
         fn build_extrinsic(from: EthTransaction) -> <Block as BlockT>::Extrinsic;
@@ -0,0 +1,38 @@
+use super::*;
+use ep_eth::{BloomInput, Log};
+
+/// Conversion of events emitted by contracts into Ethereum logs.
+///
+/// ink! contracts emit topics hashed their own way, while Ethereum tooling expects
+/// Solidity ABI topics, e.g. keccak-hashed event signature as the first one.
+/// Runtimes hosting ABI-style contracts can plug their own mapping in here.
+pub trait LogMapping {
+    /// Convert the event emitted by `contract` into a log, `None` drops the event.
+    fn to_log(contract: H160, topics: Vec<H256>, data: Vec<u8>) -> Option<Log>;
+}
+
+/// Default mapping which puts contract event topics and data into the log as they are.
+pub struct RawLogs;
+
+impl LogMapping for RawLogs {
+    fn to_log(contract: H160, topics: Vec<H256>, data: Vec<u8>) -> Option<Log> {
+        Some(Log {
+            address: contract,
+            topics,
+            data,
+        })
+    }
+}
+
+/// Compute bloom filter for the logs given.
+pub fn logs_bloom(logs: &[Log]) -> Bloom {
+    let mut bloom = Bloom::default();
+    for log in logs {
+        bloom.accrue(BloomInput::Raw(&log.address[..]));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(&topic[..]));
+        }
+    }
+
+    bloom
+}
@@ -1,6 +1,9 @@
 //! Mocked rutnime for tests
 
-use crate::{self as pallet_ethink, Config, WeightInfo};
+use crate::{
+    self as pallet_ethink, BalanceOf, Config, DepositLimit, ExecError, ExecResult, Executor,
+    FeeBudgetDepositLimit, WeightInfo,
+};
 use ep_eth::AccountId20;
 use ep_eth::EthereumSignature;
 use frame_support::{
@@ -25,7 +28,9 @@ use sp_runtime::traits::AccountIdLookup;
 use sp_runtime::traits::IdentifyAccount;
 use sp_runtime::traits::Verify;
 use sp_runtime::BuildStorage;
-use sp_runtime::{DispatchError, Perbill};
+use sp_runtime::DispatchError;
+use sp_runtime::Perbill;
+use sp_std::collections::btree_map::BTreeMap;
 
 // Well-known accounts taken from Moonbeam
 pub const ALITH: AccountId20 = AccountId20([
@@ -42,15 +47,10 @@ const MAXIMUM_BLOCK_WEIGHT: Weight =
 /// This is used to limit the maximal weight of a single extrinsic.
 const AVERAGE_ON_INITIALIZE_RATIO: Perbill = Perbill::from_percent(10);
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
-// Prints debug output of the `contracts` pallet to stdout if the node is
-// started with `-lruntime::contracts=debug`.
-const CONTRACTS_DEBUG_OUTPUT: pallet_contracts::DebugInfo =
-    pallet_contracts::DebugInfo::UnsafeDebug;
-const CONTRACTS_EVENTS: pallet_contracts::CollectEvents =
-    pallet_contracts::CollectEvents::UnsafeCollect;
 // Unit = the base number of indivisible units for balances
 const MILLIUNIT: Balance = 1_000_000_000;
 pub const ED: Balance = MILLIUNIT;
+pub const CHAIN_ID: u64 = 42;
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -216,7 +216,15 @@ impl pallet_contracts::Config for Test {
 impl Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
-    type Contracts = Contracts;
+    type Contracts = MockExecutor;
+    type OnChargeTransaction = pallet_ethink::FungibleAdapter<Balances, ()>;
+    type LogMapping = pallet_ethink::RawLogs;
+    type GasWeightMapping = pallet_ethink::FixedGasWeightMapping<ConstU64<1>, ConstU64<4>>;
+    type BalanceConverter = pallet_ethink::DecimalsConverter<NativeDecimals>;
+    type DepositLimit = MockDepositLimit;
+    type TransactionRetention = TransactionRetention;
+    type ChainId = ConstU64<CHAIN_ID>;
+    type AllowUnprotectedTxs = AllowUnprotectedTxs;
     type Call = RuntimeCall;
     type WeightInfo = ();
 }
@@ -227,14 +235,180 @@ parameter_types! {
         schedule
     };
     pub static DepositPerByte: u64 = 1;
+    // Wei and native balance are the same by default
+    pub static NativeDecimals: u32 = 18;
     pub const DepositPerItem: u64 = 2;
     pub const DefaultDepositLimit: u64 = 10_000_000_000;
     pub CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(30);
+    // Whether storage deposit of transactions is limited by their fee budget
+    pub static DepositLimitByFeeBudget: bool = false;
+    pub static TransactionRetention: u64 = 3;
+    // Whether legacy transactions without replay protection are accepted
+    pub static AllowUnprotectedTxs: bool = false;
+}
+
+/// Storage deposit limit which is either none, or the fee budget, see `DepositLimitByFeeBudget`.
+pub struct MockDepositLimit;
+
+impl DepositLimit<Balance> for MockDepositLimit {
+    fn deposit_limit(fee_budget: Balance) -> Option<Balance> {
+        if DepositLimitByFeeBudget::get() {
+            FeeBudgetDepositLimit::deposit_limit(fee_budget)
+        } else {
+            None
+        }
+    }
 }
 
 // Implement ethink! executor for Contracts
 pallet_ethink::impl_executor!(Test, Contracts);
 
+/// Call made to `MockExecutor`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExecutorCall {
+    Call {
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+        gas_limit: U256,
+        storage_deposit_limit: Option<Balance>,
+    },
+    Instantiate {
+        deployer: AccountId,
+        value: Balance,
+        input: Vec<u8>,
+        salt: Vec<u8>,
+        gas_limit: U256,
+        storage_deposit_limit: Option<Balance>,
+    },
+}
+
+parameter_types! {
+    // Calls made to the executor so far
+    pub static ExecutorCalls: Vec<ExecutorCall> = Vec::new();
+    // Mock contracts, along with the result every call to them returns
+    pub static MockContracts: BTreeMap<AccountId, Result<ExecResult, ExecError>> = BTreeMap::new();
+    // Address and result of the next contract instantiation, if it is mocked
+    pub static MockInstantiation: Option<(AccountId, Result<ExecResult, ExecError>)> = None;
+}
+
+/// Executor which records all the calls made to it.
+/// Calls to mock contracts and mock instantiations return the results set for them,
+/// everything else is executed by `pallet_contracts`.
+pub struct MockExecutor;
+
+impl Executor<Test> for MockExecutor {
+    fn code_at(address: &AccountId) -> Option<Vec<u8>> {
+        if MockContracts::get().contains_key(address) {
+            return Some(Vec::new());
+        }
+        <Contracts as Executor<Test>>::code_at(address)
+    }
+
+    fn code_hash_at(address: &AccountId) -> Option<H256> {
+        if MockContracts::get().contains_key(address) {
+            return Some(H256::zero());
+        }
+        <Contracts as Executor<Test>>::code_hash_at(address)
+    }
+
+    fn storage_at(address: &AccountId, key: Vec<u8>) -> Option<Vec<u8>> {
+        <Contracts as Executor<Test>>::storage_at(address, key)
+    }
+
+    fn set_storage(
+        address: &AccountId,
+        key: Vec<u8>,
+        value: Option<Vec<u8>>,
+    ) -> Result<(), DispatchError> {
+        <Contracts as Executor<Test>>::set_storage(address, key, value)
+    }
+
+    fn clear_storage(address: &AccountId) -> Result<(), DispatchError> {
+        <Contracts as Executor<Test>>::clear_storage(address)
+    }
+
+    fn replace_code(
+        uploader: &AccountId,
+        address: &AccountId,
+        code: Vec<u8>,
+    ) -> Result<(), DispatchError> {
+        <Contracts as Executor<Test>>::replace_code(uploader, address, code)
+    }
+
+    fn is_contract(who: &AccountId) -> bool {
+        MockContracts::get().contains_key(who) || <Contracts as Executor<Test>>::is_contract(who)
+    }
+
+    fn call(
+        from: &AccountId,
+        to: AccountId,
+        value: BalanceOf<Test>,
+        data: Vec<u8>,
+        gas_limit: U256,
+        storage_deposit_limit: Option<BalanceOf<Test>>,
+    ) -> Result<ExecResult, ExecError> {
+        ExecutorCalls::mutate(|calls| {
+            calls.push(ExecutorCall::Call {
+                from: *from,
+                to,
+                value,
+                data: data.clone(),
+                gas_limit,
+                storage_deposit_limit,
+            })
+        });
+        match MockContracts::get().get(&to) {
+            // Mocked contract runs out of gas if given less than it requires
+            Some(Ok(result)) if result.gas_required > gas_limit => Err(ExecError {
+                error: pallet_contracts::Error::<Test>::OutOfGas.into(),
+                gas_used: gas_limit,
+            }),
+            Some(result) => result.clone(),
+            None => <Contracts as Executor<Test>>::call(
+                from,
+                to,
+                value,
+                data,
+                gas_limit,
+                storage_deposit_limit,
+            ),
+        }
+    }
+
+    fn instantiate(
+        deployer: &AccountId,
+        value: BalanceOf<Test>,
+        input: Vec<u8>,
+        salt: Vec<u8>,
+        gas_limit: U256,
+        storage_deposit_limit: Option<BalanceOf<Test>>,
+    ) -> Result<(AccountId, ExecResult), ExecError> {
+        ExecutorCalls::mutate(|calls| {
+            calls.push(ExecutorCall::Instantiate {
+                deployer: *deployer,
+                value,
+                input: input.clone(),
+                salt: salt.clone(),
+                gas_limit,
+                storage_deposit_limit,
+            })
+        });
+        match MockInstantiation::take() {
+            Some((address, result)) => result.map(|result| (address, result)),
+            None => <Contracts as Executor<Test>>::instantiate(
+                deployer,
+                value,
+                input,
+                salt,
+                gas_limit,
+                storage_deposit_limit,
+            ),
+        }
+    }
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 pub fn new_test_ext() -> sp_io::TestExternalities {
     use pallet_contracts::{Code, CollectEvents, DebugInfo};
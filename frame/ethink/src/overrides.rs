@@ -0,0 +1,96 @@
+use super::*;
+use sp_std::collections::btree_map::BTreeMap;
+
+/// Account state to assume for a dry run, instead of the actual one.
+#[derive(Clone, Default, Eq, PartialEq, RuntimeDebug, Encode, Decode, TypeInfo)]
+pub struct StateOverride {
+    /// Balance of the account, in wei
+    pub balance: Option<U256>,
+    /// Nonce of the account
+    pub nonce: Option<U256>,
+    /// Code to replace the code of the contract with
+    pub code: Option<Vec<u8>>,
+    /// Values of the EVM storage slots of the contract, with all the other slots emptied
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Values of the EVM storage slots of the contract, with all the other slots kept
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+impl<T> Pallet<T>
+where
+    T: Config,
+    T::AccountId: From<H160> + AsRef<[u8]>,
+    T::Contracts: Executor<T>,
+{
+    /// Run `f` with the state overrides given applied.
+    /// Overrides are rolled back afterwards, along with any changes made by `f`.
+    pub fn with_overrides<R>(
+        overrides: Vec<(H160, StateOverride)>,
+        f: impl FnOnce() -> Result<R, DispatchError>,
+    ) -> Result<R, DispatchError> {
+        with_transaction(|| {
+            let result = overrides
+                .into_iter()
+                .try_for_each(|(address, state)| Self::apply_override(address, state))
+                .and_then(|_| f());
+            TransactionOutcome::Rollback(result)
+        })
+    }
+
+    /// Apply the state override to the account.
+    ///
+    /// Code can only be overridden for an existing contract, as there is no contract
+    /// to run it otherwise, the same goes for storage.
+    fn apply_override(address: H160, state: StateOverride) -> Result<(), DispatchError> {
+        let who = T::AccountId::from(address);
+        if let Some(balance) = state.balance {
+            T::Currency::set_balance(&who, Self::wei_to_native(balance)?);
+        }
+        if let Some(nonce) = state.nonce {
+            let nonce: u128 = nonce.try_into().unwrap_or(u128::MAX);
+            frame_system::Account::<T>::mutate(&who, |account| {
+                account.nonce = nonce.unique_saturated_into()
+            });
+        }
+        if let Some(code) = state.code {
+            T::Contracts::replace_code(&Self::code_uploader(), &who, code)?;
+        }
+        if let Some(slots) = state.state {
+            T::Contracts::clear_storage(&who)?;
+            Self::set_slots(&who, slots)?;
+        }
+        if let Some(slots) = state.state_diff {
+            Self::set_slots(&who, slots)?;
+        }
+
+        Ok(())
+    }
+
+    /// Account the overridden code is uploaded on behalf of, funded for the deposit,
+    /// so that neither the caller nor the contract pays for it. As overrides are only
+    /// applied within a dry run, the funds are rolled back along with the code.
+    fn code_uploader() -> T::AccountId {
+        let uploader = T::AccountId::from(H160::from_slice(
+            &sp_io::hashing::keccak_256(b"ethink:state-override")[12..],
+        ));
+        let funds = BalanceOf::<T>::max_value().saturating_sub(T::Currency::total_issuance());
+        T::Currency::set_balance(&uploader, funds);
+        uploader
+    }
+
+    /// Store the values at the EVM storage slots of the contract.
+    ///
+    /// This is the reverse of [`Self::storage_at`]: values are stored with their leading
+    /// zero bytes stripped, and zero values remove the slot, as empty slots read as zero.
+    fn set_slots(who: &T::AccountId, slots: BTreeMap<H256, H256>) -> Result<(), DispatchError> {
+        slots.into_iter().try_for_each(|(slot, value)| {
+            let key = Self::storage_key(U256::from_big_endian(slot.as_bytes()));
+            let value = value.as_bytes();
+            let value = value
+                .iter()
+                .position(|b| *b != 0)
+                .map(|start| value[start..].to_vec());
+            T::Contracts::set_storage(who, key, value)
+        })
+    }
+}
@@ -1,14 +1,27 @@
 use crate::{mock::*, System};
 
-use crate::{self as pallet_ethink, Pallet, Weight};
-use ep_eth::{compose_and_sign_tx, AccountId20, EthTxInput, TransactionAction};
-use frame_support::assert_ok;
-use pallet_contracts::{Code, CollectEvents, DebugInfo};
-use sp_runtime::BuildStorage;
+use crate::{self as pallet_ethink, Pallet, StateOverride, Weight};
+use ep_eth::{
+    compose_and_sign_eip1559_tx, compose_and_sign_eip2930_tx, compose_and_sign_tx, AccountId20,
+    EthTransaction, EthTxInput, EthereumSigner, TransactionAction,
+};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchInfo};
+use scale_codec::Encode;
+use sp_core::{ecdsa, Pair, H160, H256, U256};
+use sp_runtime::{
+    traits::IdentifyAccount,
+    transaction_validity::{InvalidTransaction, TransactionValidityError},
+    BuildStorage,
+};
 
 mod test_utils {
-    use crate::{mock::Test, tests::AccountId20, Config};
+    use crate::{
+        mock::{Contracts, Test, ALITH},
+        tests::{AccountId20, GAS_LIMIT},
+        Config,
+    };
     use frame_support::traits::fungible::Mutate;
+    use pallet_contracts::{Code, CollectEvents, DebugInfo};
 
     pub fn set_balance(who: &AccountId20, amount: u128) {
         let _ = <Test as Config>::Currency::set_balance(who, amount);
@@ -16,8 +29,33 @@ mod test_utils {
     pub fn get_balance(who: &AccountId20) -> u128 {
         <Test as Config>::Currency::free_balance(who)
     }
+    /// Instantiate contract from the given WAT fixture on behalf of Alith
+    pub fn deploy_fixture(wat: &str, value: u128) -> AccountId20 {
+        let code = wat::parse_str(wat).unwrap();
+        Contracts::bare_instantiate(
+            ALITH,
+            value,
+            GAS_LIMIT,
+            None,
+            Code::Upload(code),
+            vec![],
+            vec![],
+            DebugInfo::Skip,
+            CollectEvents::Skip,
+        )
+        .result
+        .expect("Failed to instantiate contract")
+        .account_id
+    }
 }
 
+// Transaction builders for all supported envelope types
+const TX_BUILDERS: [fn(EthTxInput) -> EthTransaction; 3] = [
+    compose_and_sign_tx,
+    compose_and_sign_eip2930_tx,
+    compose_and_sign_eip1559_tx,
+];
+
 #[derive(Default)]
 pub struct ExtBuilder;
 
@@ -68,26 +106,110 @@ const CONTRACT_CODE: &str = r#"
 )
 "#;
 
+// This is a simple Wasm contract which does nothing when called
+const NOOP_CONTRACT_CODE: &str = r#"
+(module
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy"))
+	(func (export "call"))
+)
+"#;
+
+// This is a simple Wasm contract which emits an event when called
+const EVENT_CONTRACT_CODE: &str = r#"
+(module
+	(import "seal0" "seal_deposit_event" (func $seal_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; SCALE-encoded topics: a vec with a single 32 bytes topic
+	(data (i32.const 0) "\04"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+	)
+	;; event data
+	(data (i32.const 33) "\ab\cd")
+
+	(func (export "deploy"))
+	(func (export "call")
+		(call $seal_deposit_event
+			(i32.const 0)	;; Pointer to topics
+			(i32.const 33)	;; Length of topics
+			(i32.const 33)	;; Pointer to data
+			(i32.const 2)	;; Length of data
+		)
+	)
+)
+"#;
+
+// This is a simple Wasm contract which stores some values upon deployment
+const STORAGE_CONTRACT_CODE: &str = r#"
+(module
+	(import "seal2" "set_storage" (func $seal_set_storage (param i32 i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; ink! root storage key
+	(data (i32.const 0) "\00\00\00\00")
+	;; raw 32 bytes storage key
+	(data (i32.const 4)
+		"\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff"
+		"\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff"
+	)
+	;; values
+	(data (i32.const 36) "\2a\2b\2c")
+	;; ink! `Mapping` entry key: root key of the mapping followed by SCALE-encoded entry key
+	(data (i32.const 40) "\01\00\00\00\07")
+
+	(func (export "deploy")
+		(drop (call $seal_set_storage
+			(i32.const 0)	;; Pointer to root key
+			(i32.const 4)	;; Length of root key
+			(i32.const 36)	;; Pointer to value
+			(i32.const 1)	;; Length of value
+		))
+		(drop (call $seal_set_storage
+			(i32.const 4)	;; Pointer to raw key
+			(i32.const 32)	;; Length of raw key
+			(i32.const 37)	;; Pointer to value
+			(i32.const 1)	;; Length of value
+		))
+		(drop (call $seal_set_storage
+			(i32.const 40)	;; Pointer to mapping entry key
+			(i32.const 5)	;; Length of mapping entry key
+			(i32.const 38)	;; Pointer to value
+			(i32.const 1)	;; Length of value
+		))
+	)
+	(func (export "call"))
+)
+"#;
+
+// This is a simple Wasm contract which reverts with some data when called
+const REVERT_CONTRACT_CODE: &str = r#"
+(module
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; revert data
+	(data (i32.const 0) "\de\ad\be\ef")
+
+	(func (export "deploy"))
+	(func (export "call")
+		(call $seal_return
+			(i32.const 1)	;; REVERT flag
+			(i32.const 0)	;; Pointer to data
+			(i32.const 4)	;; Length of data
+		)
+		(unreachable) ;; seal_return never returns
+	)
+)
+"#;
+
 #[test]
 fn calling_contract_account_executes_it() {
-    let wasm = wat::parse_str(CONTRACT_CODE).unwrap();
     ExtBuilder::default().build().execute_with(|| {
         let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
         // Instantiate contract and deposit balance (ED) to it
-        let contract_addr = Contracts::bare_instantiate(
-            ALITH,
-            0,
-            GAS_LIMIT,
-            None,
-            Code::Upload(wasm),
-            vec![],
-            vec![],
-            DebugInfo::Skip,
-            CollectEvents::Skip,
-        )
-        .result
-        .expect("Failed to instantiate contract")
-        .account_id;
+        let contract_addr = test_utils::deploy_fixture(CONTRACT_CODE, 0);
 
         // Compose transaction
         let input = EthTxInput {
@@ -113,3 +235,1524 @@ fn calling_contract_account_executes_it() {
         assert_eq!(nonce, 1);
     });
 }
+
+#[test]
+fn wasm_input_is_split_into_code_and_constructor_data() {
+    let wasm = wat::parse_str(CONTRACT_CODE).unwrap();
+    // Constructor selector with some SCALE-encoded argument
+    let ctor = vec![0x9b, 0xae, 0x9d, 0x5e, 0x01];
+    let input = [wasm.encode(), ctor.clone()].concat();
+    assert_eq!(
+        crate::split_wasm_input(&input),
+        Some((wasm.clone(), ctor.clone()))
+    );
+    // No constructor data
+    assert_eq!(
+        crate::split_wasm_input(&wasm.encode()),
+        Some((wasm.clone(), vec![]))
+    );
+    // Not a wasm blob
+    assert_eq!(crate::split_wasm_input(&ctor.encode()), None);
+    // Code without the length prefix
+    assert_eq!(crate::split_wasm_input(&wasm), None);
+}
+
+#[test]
+fn constructor_data_resembling_wasm_section_is_kept_intact() {
+    let wasm = wat::parse_str(CONTRACT_CODE).unwrap();
+    // Selectors which read as a well-formed wasm section header (id, size) followed by its body
+    for ctor in [
+        vec![0x05, 0x02, 0xaa, 0xbb],
+        vec![0x00, 0x02, 0xaa, 0xbb],
+        vec![0x0b, 0x01, 0x00, 0x01],
+    ] {
+        let input = [wasm.encode(), ctor.clone()].concat();
+        assert_eq!(crate::split_wasm_input(&input), Some((wasm.clone(), ctor)));
+    }
+}
+
+#[test]
+fn create_transaction_instantiates_contract() {
+    let wasm = wat::parse_str(CONTRACT_CODE).unwrap();
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        // Compose transaction
+        let input = EthTxInput {
+            action: TransactionAction::Create,
+            data: wasm.encode().into(),
+            ..Default::default()
+        };
+        let eth_tx = compose_and_sign_tx(input);
+        let tx_hash = eth_tx.hash();
+
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        // Deploy contract
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        // Contract address should be reported
+        let address = System::<Test>::events()
+            .into_iter()
+            .find_map(|r| match r.event {
+                RuntimeEvent::Ethink(pallet_ethink::Event::ContractCreated {
+                    from,
+                    address,
+                    tx_hash: h,
+                    ..
+                }) if from == ALITH.into() && h == tx_hash => Some(address),
+                _ => None,
+            })
+            .expect("no ContractCreated event deposited");
+        // and contract should be instantiated at that address
+        assert!(Contracts::code_hash(&address.into()).is_some());
+        // Check that sender account nonce incremented
+        let nonce: u64 = System::<Test>::account_nonce(ALITH).into();
+        assert_eq!(nonce, 1);
+    });
+}
+
+#[test]
+fn signer_is_recovered_for_all_envelope_types() {
+    let signer = ecdsa::Pair::generate().0;
+    let sender: H160 = EthereumSigner::from(signer.public()).into_account().into();
+
+    for compose in TX_BUILDERS {
+        let input = EthTxInput {
+            signer: signer.clone(),
+            ..Default::default()
+        };
+        let eth_tx = compose(input);
+        assert_eq!(Pallet::<Test>::check_eth_signature(&eth_tx), Ok(sender));
+    }
+}
+
+#[test]
+fn transfer_works_for_all_envelope_types() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+
+        for (i, compose) in TX_BUILDERS.into_iter().enumerate() {
+            // Compose transaction
+            let input = EthTxInput {
+                action: TransactionAction::Call(BALTATHAR.into()),
+                value: ED,
+                data: vec![].into(),
+                ..Default::default()
+            };
+            let eth_tx = compose(input);
+
+            let origin =
+                RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+            // Transfer ED to Baltathar
+            assert_ok!(Ethink::transact(origin, eth_tx));
+            assert_eq!(test_utils::get_balance(&BALTATHAR), ED * (i as u128 + 1));
+        }
+        // Check that sender account nonce incremented for every transaction
+        let nonce: u64 = System::<Test>::account_nonce(ALITH).into();
+        assert_eq!(nonce, 3);
+    });
+}
+
+#[test]
+fn transactions_for_another_chain_are_rejected() {
+    let signer = ecdsa::Pair::generate().0;
+    let sender: H160 = EthereumSigner::from(signer.public()).into_account().into();
+    let check = |tx| pallet_ethink::Call::<Test>::transact { tx }.check_self_contained();
+
+    for compose in TX_BUILDERS {
+        let input = |chain_id| EthTxInput {
+            signer: signer.clone(),
+            chain_id,
+            ..Default::default()
+        };
+        // Transaction for this chain is accepted
+        assert_eq!(check(compose(input(Some(CHAIN_ID)))), Some(Ok(sender)));
+        // Transaction for another chain is rejected
+        assert_eq!(
+            check(compose(input(Some(CHAIN_ID + 1)))),
+            Some(Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Custom(
+                    pallet_ethink::TransactionValidationError::InvalidChainId as u8
+                )
+            )))
+        );
+    }
+    // Legacy transaction without replay protection is rejected by default
+    let tx = compose_and_sign_tx(EthTxInput {
+        signer: signer.clone(),
+        chain_id: None,
+        ..Default::default()
+    });
+    assert_eq!(
+        check(tx.clone()),
+        Some(Err(TransactionValidityError::Invalid(
+            InvalidTransaction::Custom(
+                pallet_ethink::TransactionValidationError::UnprotectedTransaction as u8
+            )
+        )))
+    );
+    // unless it is allowed explicitly
+    AllowUnprotectedTxs::set(true);
+    assert_eq!(check(tx), Some(Ok(sender)));
+}
+
+#[test]
+fn nonce_is_checked_for_pool_and_dispatch() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let sender: H160 = ALITH.into();
+        let info = DispatchInfo::default();
+        // Make Alith's account nonce = 1
+        System::<Test>::inc_account_nonce(ALITH);
+        let call = |nonce| pallet_ethink::Call::<Test>::transact {
+            tx: compose_and_sign_tx(EthTxInput {
+                nonce,
+                gas_price: 1,
+                gas_limit: Weight::from_parts(21_000, 0),
+                ..Default::default()
+            }),
+        };
+        // Stale nonce is rejected
+        assert_eq!(
+            call(0).validate_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Stale.into()))
+        );
+        assert_eq!(
+            call(0).pre_dispatch_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Stale.into()))
+        );
+        // Current nonce is accepted and requires nothing
+        let valid = call(1)
+            .validate_self_contained(&sender, &info, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(valid.provides, vec![(sender, U256::from(1)).encode()]);
+        assert!(valid.requires.is_empty());
+        assert_eq!(
+            call(1).pre_dispatch_self_contained(&sender, &info, 0),
+            Some(Ok(()))
+        );
+        // Future nonce is queued in the pool, waiting for the previous one
+        let valid = call(3)
+            .validate_self_contained(&sender, &info, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(valid.provides, vec![(sender, U256::from(3)).encode()]);
+        assert_eq!(valid.requires, vec![(sender, U256::from(2)).encode()]);
+        // but can't be dispatched yet
+        assert_eq!(
+            call(3).pre_dispatch_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Future.into()))
+        );
+    });
+}
+
+#[test]
+fn transfer_is_charged_base_fee() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        // Balance transfer costs the base fee, regardless of the gas limit
+        let input = EthTxInput {
+            action: TransactionAction::Call(BALTATHAR.into()),
+            value: ED,
+            gas_price: 2,
+            gas_limit: Weight::from_parts(50_000, 0),
+            data: vec![].into(),
+            ..Default::default()
+        };
+        let eth_tx = compose_and_sign_tx(input);
+
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        assert_eq!(test_utils::get_balance(&BALTATHAR), ED);
+        assert_eq!(
+            test_utils::get_balance(&ALITH),
+            10_000_000_000 - ED - 42_000
+        );
+    });
+}
+
+#[test]
+fn unused_gas_is_refunded() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000_000);
+        // Instantiate contract and deposit balance (ED) to it
+        let contract_addr = test_utils::deploy_fixture(NOOP_CONTRACT_CODE, 0);
+        let balance_before = test_utils::get_balance(&ALITH);
+        // Compose transaction
+        let gas_limit = 1_000_000_000_000u64;
+        let input = EthTxInput {
+            action: TransactionAction::Call(contract_addr.into()),
+            gas_price: 1,
+            gas_limit: Weight::from_parts(gas_limit, 0),
+            data: vec![].into(),
+            ..Default::default()
+        };
+        let eth_tx = compose_and_sign_tx(input);
+
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        // Fee should be charged only for the gas actually used by the contract
+        let fee = balance_before - test_utils::get_balance(&ALITH);
+        assert!(fee > 0);
+        assert!(fee < gas_limit as u128);
+    });
+}
+
+#[test]
+fn transaction_fails_if_fee_is_unaffordable() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let input = EthTxInput {
+            action: TransactionAction::Call(BALTATHAR.into()),
+            value: ED,
+            gas_price: 1_000_000,
+            gas_limit: Weight::from_parts(21_000, 0),
+            data: vec![].into(),
+            ..Default::default()
+        };
+        let eth_tx = compose_and_sign_tx(input);
+
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_noop!(
+            Ethink::transact(origin, eth_tx),
+            pallet_ethink::Error::<Test>::InsufficientBalanceForFee
+        );
+    });
+}
+
+#[test]
+fn transaction_sender_should_afford_value_and_fee() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 1_000_000 + ED);
+        let sender: H160 = ALITH.into();
+        let info = DispatchInfo::default();
+        let call = |value, gas_price| pallet_ethink::Call::<Test>::transact {
+            tx: compose_and_sign_tx(EthTxInput {
+                nonce: 0,
+                action: TransactionAction::Call(BALTATHAR.into()),
+                value,
+                gas_price,
+                gas_limit: Weight::from_parts(21_000, 0),
+                data: vec![].into(),
+                ..Default::default()
+            }),
+        };
+        // Sender can afford value + fee
+        assert!(call(ED, 10)
+            .validate_self_contained(&sender, &info, 0)
+            .unwrap()
+            .is_ok());
+        // Sender can't afford value + fee
+        assert_eq!(
+            call(ED, 100).validate_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Payment.into()))
+        );
+        // Sender can't afford value
+        assert_eq!(
+            call(2 * ED, 1).validate_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Payment.into()))
+        );
+        // Fee can't take the existential deposit, though the value can
+        assert_eq!(
+            call(0, 100).validate_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Payment.into()))
+        );
+    });
+}
+
+#[test]
+fn balance_is_rechecked_upon_dispatch() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 3 * ED);
+        let sender: H160 = ALITH.into();
+        let info = DispatchInfo::default();
+        let tx = |nonce| {
+            compose_and_sign_tx(EthTxInput {
+                nonce,
+                action: TransactionAction::Call(BALTATHAR.into()),
+                value: 2 * ED,
+                gas_price: 1,
+                gas_limit: Weight::from_parts(21_000, 0),
+                ..Default::default()
+            })
+        };
+        let call = |nonce| pallet_ethink::Call::<Test>::transact { tx: tx(nonce) };
+        // Each transaction is affordable on its own, so both get into the pool
+        for nonce in [0, 1] {
+            assert!(call(nonce)
+                .validate_self_contained(&sender, &info, 0)
+                .unwrap()
+                .is_ok());
+        }
+        // First one spends the balance the second one needs
+        assert_eq!(
+            call(0).pre_dispatch_self_contained(&sender, &info, 0),
+            Some(Ok(()))
+        );
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, tx(0)));
+        assert_eq!(
+            call(1).pre_dispatch_self_contained(&sender, &info, 0),
+            Some(Err(InvalidTransaction::Payment.into()))
+        );
+    });
+}
+
+#[test]
+fn sending_whole_balance_reaps_sender_account() {
+    ExtBuilder::default().build().execute_with(|| {
+        let balance = 10 * ED;
+        let _ = test_utils::set_balance(&ALITH, balance);
+        let gas_limit = 30_000;
+        // Send everything but the maximum fee, as Ethereum wallets do
+        let value = balance - gas_limit;
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(BALTATHAR.into()),
+            value,
+            gas_price: 1,
+            gas_limit: Weight::from_parts(gas_limit as u64, 0),
+            ..Default::default()
+        });
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        // Value is transferred, and the fee refund is dropped along with the sender account
+        assert_eq!(test_utils::get_balance(&BALTATHAR), value);
+        assert_eq!(test_utils::get_balance(&ALITH), 0);
+        assert!(!System::<Test>::account_exists(&ALITH));
+    });
+}
+
+#[test]
+fn priority_grows_with_gas_price() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let sender: H160 = ALITH.into();
+        let info = DispatchInfo::default();
+        let validate = |compose: fn(EthTxInput) -> EthTransaction, gas_price| {
+            pallet_ethink::Call::<Test>::transact {
+                tx: compose(EthTxInput {
+                    nonce: 0,
+                    gas_price,
+                    gas_limit: Weight::from_parts(21_000, 0),
+                    ..Default::default()
+                }),
+            }
+            .validate_self_contained(&sender, &info, 0)
+            .unwrap()
+            .unwrap()
+        };
+
+        for compose in TX_BUILDERS {
+            let (cheap, pricey) = (validate(compose, 1), validate(compose, 2));
+            // Pricier transaction with the same nonce replaces the cheaper one in the pool
+            assert_eq!(cheap.provides, pricey.provides);
+            assert!(pricey.priority > cheap.priority);
+        }
+    });
+}
+
+#[test]
+fn priority_follows_gas_price_in_native_balance() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let sender: H160 = ALITH.into();
+        let info = DispatchInfo::default();
+        let priority = |gas_price, gas_limit| {
+            pallet_ethink::Call::<Test>::transact {
+                tx: compose_and_sign_tx(EthTxInput {
+                    nonce: 0,
+                    gas_price,
+                    gas_limit: Weight::from_parts(gas_limit, 0),
+                    ..Default::default()
+                }),
+            }
+            .validate_self_contained(&sender, &info, 0)
+            .unwrap()
+            .unwrap()
+            .priority
+        };
+
+        // Gas limit doesn't matter, only the price per gas does
+        assert_eq!(priority(2, 42_000), priority(2, 21_000));
+        // Same gas price in native balance yields the same priority, whatever the decimals are
+        let wei_priority = priority(2, 21_000);
+        NativeDecimals::set(12);
+        assert_eq!(priority(2_000_000, 21_000), wei_priority);
+    });
+}
+
+#[test]
+fn low_gas_tx_with_higher_price_replaces_high_gas_one() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10u128.pow(18));
+        let sender: H160 = ALITH.into();
+        let validate = |gas_price, gas_limit: u64| {
+            let tx = compose_and_sign_tx(EthTxInput {
+                nonce: 0,
+                action: TransactionAction::Call(BALTATHAR.into()),
+                gas_price,
+                gas_limit: Weight::from_parts(gas_limit, 0),
+                ..Default::default()
+            });
+            // Weight declared by the call, as the pool sees it
+            let info = DispatchInfo {
+                weight: Ethink::transact_weight(&tx),
+                ..Default::default()
+            };
+            pallet_ethink::Call::<Test>::transact { tx }
+                .validate_self_contained(&sender, &info, 0)
+                .unwrap()
+                .unwrap()
+        };
+
+        // Pending call spending lots of gas
+        let pending = validate(1, Ethink::block_gas_limit().as_u64());
+        // Cancel by a plain transfer with the same nonce at a higher price
+        let cancel = validate(2, 21_000);
+        assert_eq!(cancel.provides, pending.provides);
+        assert!(cancel.priority > pending.priority);
+    });
+}
+
+#[test]
+fn gas_below_minimum_is_rejected() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let sender: H160 = ALITH.into();
+        let info = DispatchInfo::default();
+        let call = |gas_price, gas_limit| pallet_ethink::Call::<Test>::transact {
+            tx: compose_and_sign_tx(EthTxInput {
+                nonce: 0,
+                action: TransactionAction::Call(BALTATHAR.into()),
+                gas_price,
+                gas_limit: Weight::from_parts(gas_limit, 0),
+                ..Default::default()
+            }),
+        };
+        let rejected = Some(Err(InvalidTransaction::Payment.into()));
+
+        // Minimum gas price and base gas are accepted
+        assert!(call(1, 21_000)
+            .validate_self_contained(&sender, &info, 0)
+            .unwrap()
+            .is_ok());
+        assert_eq!(
+            call(1, 21_000).pre_dispatch_self_contained(&sender, &info, 0),
+            Some(Ok(()))
+        );
+        // Free transaction is rejected
+        assert_eq!(
+            call(0, 21_000).validate_self_contained(&sender, &info, 0),
+            rejected
+        );
+        assert_eq!(
+            call(0, 21_000).pre_dispatch_self_contained(&sender, &info, 0),
+            rejected
+        );
+        // Gas limit below the base gas is rejected
+        assert_eq!(
+            call(1, 20_999).validate_self_contained(&sender, &info, 0),
+            rejected
+        );
+        assert_eq!(
+            call(1, 20_999).pre_dispatch_self_contained(&sender, &info, 0),
+            rejected
+        );
+    });
+}
+
+#[test]
+fn executed_transactions_are_stored_in_eth_block() {
+    use crate::{CurrentBlock, CurrentReceipts, CurrentTransactionStatuses, Receipt};
+    use frame_support::traits::Hooks;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        let txs: Vec<_> = TX_BUILDERS
+            .into_iter()
+            .map(|compose| {
+                compose(EthTxInput {
+                    action: TransactionAction::Call(BALTATHAR.into()),
+                    value: ED,
+                    data: vec![].into(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        for tx in txs.clone() {
+            assert_ok!(Ethink::transact(origin.clone(), tx));
+        }
+        Ethink::on_finalize(1);
+
+        let block = CurrentBlock::<Test>::get().unwrap();
+        assert_eq!(block.header.number, U256::from(1));
+        assert_eq!(block.transactions, txs);
+        let statuses = CurrentTransactionStatuses::<Test>::get().unwrap();
+        assert_eq!(statuses.len(), txs.len());
+        for (i, (status, tx)) in statuses.iter().zip(&txs).enumerate() {
+            assert_eq!(status.transaction_hash, tx.hash());
+            assert_eq!(status.transaction_index, i as u32);
+            assert_eq!(status.from, ALITH.into());
+            assert_eq!(status.to, Some(BALTATHAR.into()));
+            assert_eq!(status.contract_address, None);
+        }
+        // Receipt type follows transaction type, gas used is cumulative
+        let receipts = CurrentReceipts::<Test>::get().unwrap();
+        let mut used_gas = U256::zero();
+        for (receipt, tx) in receipts.iter().zip(&txs) {
+            let data = match (receipt, tx) {
+                (Receipt::Legacy(d), EthTransaction::Legacy(_))
+                | (Receipt::EIP2930(d), EthTransaction::EIP2930(_))
+                | (Receipt::EIP1559(d), EthTransaction::EIP1559(_)) => d,
+                _ => panic!("receipt type does not match transaction type"),
+            };
+            assert_eq!(data.status_code, 1);
+            assert!(data.used_gas > used_gas);
+            used_gas = data.used_gas;
+        }
+        assert_eq!(block.header.gas_used, used_gas);
+
+        // Next block starts from scratch
+        Ethink::on_initialize(2);
+        assert!(CurrentBlock::<Test>::get().is_none());
+        assert!(CurrentReceipts::<Test>::get().is_none());
+        assert!(CurrentTransactionStatuses::<Test>::get().is_none());
+    });
+}
+
+#[test]
+fn transaction_locations_are_kept_for_retention_window() {
+    use crate::TransactionLocation;
+    use frame_support::traits::Hooks;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let transfer = |nonce, extrinsic_index| {
+            let eth_tx = compose_and_sign_tx(EthTxInput {
+                nonce,
+                action: TransactionAction::Call(BALTATHAR.into()),
+                value: ED,
+                gas_limit: Weight::from_parts(21_000, 0),
+                data: vec![].into(),
+                ..Default::default()
+            });
+            let tx_hash = eth_tx.hash();
+            System::<Test>::set_extrinsic_index(extrinsic_index);
+            let origin =
+                RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+            assert_ok!(Ethink::transact(origin, eth_tx));
+            tx_hash
+        };
+        let location = |block_number: u64, extrinsic_index, transaction_index| {
+            Some(TransactionLocation {
+                block_number: block_number.into(),
+                extrinsic_index,
+                transaction_index,
+            })
+        };
+
+        let first = transfer(1, 1);
+        let second = transfer(2, 3);
+        assert_eq!(Ethink::transaction_location(first), location(1, 1, 0));
+        assert_eq!(Ethink::transaction_location(second), location(1, 3, 1));
+        // Locations are kept for 3 blocks
+        assert_eq!(TransactionRetention::get(), 3);
+        for n in 2..=3 {
+            Ethink::on_initialize(n);
+            System::<Test>::set_block_number(n);
+        }
+        let third = transfer(3, 1);
+        assert_eq!(Ethink::transaction_location(first), location(1, 1, 0));
+        assert_eq!(Ethink::transaction_location(third), location(3, 1, 0));
+
+        // Transactions of the block leaving the retention window are forgotten
+        Ethink::on_initialize(4);
+        assert_eq!(Ethink::transaction_location(first), None);
+        assert_eq!(Ethink::transaction_location(second), None);
+        assert_eq!(Ethink::transaction_location(third), location(3, 1, 0));
+        assert_eq!(Ethink::transaction_location(H256::repeat_byte(1)), None);
+    });
+}
+
+#[test]
+#[should_panic(expected = "TransactionRetention must be non-zero")]
+fn zero_transaction_retention_is_rejected() {
+    use frame_support::traits::Hooks;
+
+    TransactionRetention::set(0);
+    Ethink::integrity_test();
+}
+
+#[test]
+fn contract_events_become_eth_logs() {
+    use crate::{CurrentReceipts, CurrentTransactionStatuses, Receipt};
+    use ep_eth::{BloomInput, Log};
+    use frame_support::traits::Hooks;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(EVENT_CONTRACT_CODE, 0);
+
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(contract_addr.into()),
+            data: vec![].into(),
+            ..Default::default()
+        });
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        Ethink::on_finalize(1);
+
+        let log = Log {
+            address: contract_addr.into(),
+            topics: vec![H256::repeat_byte(1)],
+            data: vec![0xab, 0xcd],
+        };
+        let status = &CurrentTransactionStatuses::<Test>::get().unwrap()[0];
+        assert_eq!(status.logs, vec![log.clone()]);
+        assert_eq!(status.logs_bloom, crate::logs_bloom(&[log.clone()]));
+        assert!(status
+            .logs_bloom
+            .contains_input(BloomInput::Raw(&log.address[..])));
+        assert!(status
+            .logs_bloom
+            .contains_input(BloomInput::Raw(&log.topics[0][..])));
+        let Receipt::Legacy(receipt) = &CurrentReceipts::<Test>::get().unwrap()[0] else {
+            panic!("legacy transaction should have legacy receipt");
+        };
+        assert_eq!(receipt.logs, vec![log]);
+        assert_eq!(receipt.logs_bloom, status.logs_bloom);
+    });
+}
+
+#[test]
+fn logs_are_collected_from_own_events_only() {
+    use crate::CurrentTransactionStatuses;
+    use ep_eth::Log;
+    use frame_support::traits::Hooks;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(EVENT_CONTRACT_CODE, 0);
+        // Number of events preceding the transaction takes a single byte to encode,
+        // while the transaction makes it take more
+        while System::<Test>::event_count() < 63 {
+            System::<Test>::deposit_event(frame_system::Event::<Test>::CodeUpdated);
+        }
+
+        for nonce in [0, 1] {
+            let eth_tx = compose_and_sign_tx(EthTxInput {
+                nonce,
+                action: TransactionAction::Call(contract_addr.into()),
+                data: vec![].into(),
+                ..Default::default()
+            });
+            let origin =
+                RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+            assert_ok!(Ethink::transact(origin, eth_tx));
+        }
+        assert!(System::<Test>::event_count() > 64);
+        Ethink::on_finalize(1);
+
+        // Every transaction gets the log of its own event, and nothing else
+        let log = Log {
+            address: contract_addr.into(),
+            topics: vec![H256::repeat_byte(1)],
+            data: vec![0xab, 0xcd],
+        };
+        let statuses = CurrentTransactionStatuses::<Test>::get().unwrap();
+        assert_eq!(statuses.len(), 2);
+        for status in statuses {
+            assert_eq!(status.logs, vec![log.clone()]);
+        }
+    });
+}
+
+#[test]
+fn failed_transaction_is_included_with_failed_status() {
+    use crate::{CurrentReceipts, CurrentTransactionStatuses, Receipt};
+    use frame_support::traits::Hooks;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(REVERT_CONTRACT_CODE, 0);
+        let balance = test_utils::get_balance(&ALITH);
+
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(contract_addr.into()),
+            data: vec![].into(),
+            gas_price: 1,
+            gas_limit: Weight::from_parts(1_000_000_000, 0),
+            ..Default::default()
+        });
+        let tx_hash = eth_tx.hash();
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        // Reverted transaction does not fail the extrinsic
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        Ethink::on_finalize(1);
+
+        // but still bumps the nonce and pays the fee
+        let nonce: u64 = System::<Test>::account_nonce(ALITH).into();
+        assert_eq!(nonce, 1);
+        assert!(test_utils::get_balance(&ALITH) < balance);
+        // and gets recorded with failed status and revert data
+        let status = &CurrentTransactionStatuses::<Test>::get().unwrap()[0];
+        assert_eq!(status.revert_data, vec![0xde, 0xad, 0xbe, 0xef]);
+        let Receipt::Legacy(receipt) = &CurrentReceipts::<Test>::get().unwrap()[0] else {
+            panic!("legacy transaction should have legacy receipt");
+        };
+        assert_eq!(receipt.status_code, 0);
+        assert!(!receipt.used_gas.is_zero());
+        // Failure event is deposited along with the revert data
+        System::<Test>::assert_has_event(RuntimeEvent::Ethink(pallet_ethink::Event::TxFailed {
+            from: ALITH.into(),
+            to: Some(contract_addr.into()),
+            tx_hash,
+            transaction_index: 0,
+            gas_used: receipt.used_gas,
+            reason: crate::Error::<Test>::TxReverted.into(),
+            revert_data: vec![0xde, 0xad, 0xbe, 0xef],
+        }));
+        assert!(!System::<Test>::events().iter().any(|r| matches!(
+            r.event,
+            RuntimeEvent::Ethink(pallet_ethink::Event::TxExecuted { .. })
+        )));
+    });
+}
+
+#[test]
+fn transact_weight_is_corrected_by_gas_used() {
+    use crate::WeightInfo;
+    use frame_support::dispatch::GetDispatchInfo;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(NOOP_CONTRACT_CODE, 0);
+
+        let gas_limit = 1_000_000_000_000u64;
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(contract_addr.into()),
+            gas_limit: Weight::from_parts(gas_limit, 0),
+            data: vec![].into(),
+            ..Default::default()
+        });
+        // Weight declared upfront covers all the gas transaction is allowed to spend
+        let base_weight = <() as WeightInfo>::transact();
+        let declared = pallet_ethink::Call::<Test>::transact { tx: eth_tx.clone() }
+            .get_dispatch_info()
+            .weight;
+        assert_eq!(
+            declared,
+            base_weight.saturating_add(Weight::from_parts(gas_limit, gas_limit / 4))
+        );
+        // Actual weight is the one of the gas actually used
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        let actual = Ethink::transact(origin, eth_tx)
+            .unwrap()
+            .actual_weight
+            .unwrap();
+        assert!(actual.ref_time() > base_weight.ref_time());
+        assert!(actual.ref_time() < declared.ref_time());
+    });
+}
+
+#[test]
+fn gas_maps_to_weight_both_ways() {
+    use crate::{FixedGasWeightMapping, GasWeightMapping};
+    use frame_support::traits::{ConstU64, Get};
+
+    type Mapping = FixedGasWeightMapping<ConstU64<2>, ConstU64<4>>;
+    // Gas buys ref_time and proof size
+    assert_eq!(
+        Mapping::gas_to_weight(1_000),
+        Weight::from_parts(2_000, 250)
+    );
+    // Gas needed is driven by the dominating weight component
+    assert_eq!(
+        Mapping::weight_to_gas(Weight::from_parts(2_000, 250)),
+        1_000
+    );
+    assert_eq!(Mapping::weight_to_gas(Weight::from_parts(2_001, 0)), 1_001);
+    assert_eq!(Mapping::weight_to_gas(Weight::from_parts(0, 300)), 1_200);
+    // Gas limit is bound by the scarcest weight component
+    assert_eq!(Mapping::gas_limit_for(Weight::from_parts(2_000, 100)), 400);
+    assert_eq!(
+        Mapping::gas_limit_for(Weight::from_parts(2_000, 1_000)),
+        1_000
+    );
+
+    ExtBuilder::default().build().execute_with(|| {
+        // Transaction spending the block gas limit still fits into the block
+        let max_extrinsic = <Test as frame_system::Config>::BlockWeights::get()
+            .get(frame_support::dispatch::DispatchClass::Normal)
+            .max_extrinsic
+            .unwrap();
+        let weight = Ethink::gas_to_weight(Ethink::block_gas_limit());
+        assert!(weight
+            .saturating_add(<() as crate::WeightInfo>::transact())
+            .all_lte(max_extrinsic));
+    });
+}
+
+#[test]
+fn wei_is_converted_to_native_balance() {
+    use crate::{BalanceConverter, DecimalsConverter};
+    use frame_support::traits::ConstU32;
+
+    type Converter = DecimalsConverter<ConstU32<12>>;
+    let to_native = |wei: u128| <Converter as BalanceConverter<u128>>::into_native(wei.into());
+    // A unit of native balance is worth 10^6 wei
+    assert_eq!(to_native(5_000_000), Some(5));
+    assert_eq!(
+        <Converter as BalanceConverter<u128>>::into_wei(5),
+        U256::from(5_000_000)
+    );
+    // Dust is not representable in native balance
+    assert_eq!(to_native(5_000_001), None);
+    assert_eq!(to_native(1), None);
+    // Neither is the amount overflowing native balance type
+    assert_eq!(
+        <Converter as BalanceConverter<u128>>::into_native(U256::MAX),
+        None
+    );
+}
+
+#[test]
+fn amounts_with_dust_are_rejected() {
+    NativeDecimals::set(12);
+    let signer = ecdsa::Pair::generate().0;
+    let sender: H160 = EthereumSigner::from(signer.public()).into_account().into();
+    let check = |value, gas_price| {
+        pallet_ethink::Call::<Test>::transact {
+            tx: compose_and_sign_tx(EthTxInput {
+                signer: signer.clone(),
+                value,
+                gas_price,
+                chain_id: Some(CHAIN_ID),
+                ..Default::default()
+            }),
+        }
+        .check_self_contained()
+    };
+    let rejected = Some(Err(TransactionValidityError::Invalid(
+        InvalidTransaction::Custom(
+            pallet_ethink::TransactionValidationError::UnrepresentableAmount as u8,
+        ),
+    )));
+
+    // Amounts in whole native units are accepted
+    assert_eq!(check(3_000_000, 1_000_000), Some(Ok(sender)));
+    // Value with dust is rejected
+    assert_eq!(check(3_000_001, 1_000_000), rejected);
+    // Gas price with dust is rejected
+    assert_eq!(check(3_000_000, 1_000_001), rejected);
+}
+
+#[test]
+fn value_and_fee_are_converted_to_native_balance() {
+    NativeDecimals::set(12);
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        // Balance is reported in wei
+        assert_eq!(
+            Ethink::account_balance(&ALITH.into()),
+            U256::from(10_000_000_000_000_000u128)
+        );
+        // Minimum gas price is a unit of native balance
+        assert_eq!(Ethink::min_gas_price(), U256::from(1_000_000));
+
+        let input = EthTxInput {
+            action: TransactionAction::Call(BALTATHAR.into()),
+            value: ED * 1_000_000,
+            gas_price: 2_000_000,
+            gas_limit: Weight::from_parts(21_000, 0),
+            data: vec![].into(),
+            ..Default::default()
+        };
+        let eth_tx = compose_and_sign_tx(input);
+
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        // Both value and fee are charged in native balance
+        assert_eq!(test_utils::get_balance(&BALTATHAR), ED);
+        assert_eq!(
+            test_utils::get_balance(&ALITH),
+            10_000_000_000 - ED - 42_000
+        );
+    });
+}
+
+#[test]
+fn contract_call_is_routed_to_executor() {
+    use crate::{CurrentTransactionStatuses, ExecResult, StorageDeposit};
+    use ep_eth::Log;
+    use frame_support::traits::Hooks;
+
+    let contract = AccountId20([0x11; 20]);
+    let log = Log {
+        address: contract.into(),
+        topics: vec![H256::repeat_byte(1)],
+        data: vec![0xab],
+    };
+    MockContracts::mutate(|contracts| {
+        contracts.insert(
+            contract,
+            Ok(ExecResult {
+                output: vec![0x01],
+                gas_used: 500.into(),
+                gas_required: 500.into(),
+                logs: vec![log.clone()],
+                storage_deposit: StorageDeposit::Charge(42.into()),
+                reverted: false,
+            }),
+        )
+    });
+    DepositLimitByFeeBudget::set(true);
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(contract.into()),
+            value: ED,
+            gas_price: 1,
+            gas_limit: Weight::from_parts(1_000, 0),
+            data: vec![1, 2, 3].into(),
+            ..Default::default()
+        });
+        let tx_hash = eth_tx.hash();
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        Ethink::on_finalize(1);
+
+        // Contract is called with the transaction input,
+        // and storage deposit limited by the maximum fee
+        assert_eq!(
+            ExecutorCalls::get(),
+            vec![ExecutorCall::Call {
+                from: ALITH,
+                to: contract,
+                value: ED,
+                data: vec![1, 2, 3],
+                gas_limit: 1_000.into(),
+                storage_deposit_limit: Some(1_000),
+            }]
+        );
+        // Nonce is bumped, fee is charged for the gas used only
+        let nonce: u64 = System::<Test>::account_nonce(ALITH).into();
+        assert_eq!(nonce, 1);
+        assert_eq!(test_utils::get_balance(&ALITH), 10_000_000_000 - 500);
+        // Logs and storage deposit reported by the executor are recorded
+        let status = &CurrentTransactionStatuses::<Test>::get().unwrap()[0];
+        assert_eq!(status.logs, vec![log]);
+        assert_eq!(status.storage_deposit, StorageDeposit::Charge(42.into()));
+        System::<Test>::assert_has_event(RuntimeEvent::Ethink(pallet_ethink::Event::TxExecuted {
+            from: ALITH.into(),
+            to: contract.into(),
+            tx_hash,
+            transaction_index: 0,
+            gas_used: 500.into(),
+            storage_deposit: StorageDeposit::Charge(42.into()),
+        }));
+    });
+}
+
+#[test]
+fn transfer_to_account_bypasses_executor() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(BALTATHAR.into()),
+            value: ED,
+            gas_limit: Weight::from_parts(21_000, 0),
+            data: vec![].into(),
+            ..Default::default()
+        });
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+
+        assert!(ExecutorCalls::get().is_empty());
+        assert_eq!(test_utils::get_balance(&BALTATHAR), ED);
+    });
+}
+
+#[test]
+fn create_is_routed_to_executor_with_nonce_as_salt() {
+    use crate::{CurrentTransactionStatuses, ExecResult};
+    use frame_support::traits::Hooks;
+
+    let address = AccountId20([0x22; 20]);
+    MockInstantiation::set(Some((
+        address,
+        Ok(ExecResult {
+            gas_used: 300.into(),
+            ..Default::default()
+        }),
+    )));
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        System::<Test>::inc_account_nonce(ALITH);
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Create,
+            gas_limit: Weight::from_parts(1_000, 0),
+            data: vec![0xc0, 0xde].into(),
+            ..Default::default()
+        });
+        let tx_hash = eth_tx.hash();
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        Ethink::on_finalize(1);
+
+        // Nonce the sender had before the transaction is used as a salt
+        assert_eq!(
+            ExecutorCalls::get(),
+            vec![ExecutorCall::Instantiate {
+                deployer: ALITH,
+                value: 0,
+                input: vec![0xc0, 0xde],
+                salt: 1u64.encode(),
+                gas_limit: 1_000.into(),
+                storage_deposit_limit: None,
+            }]
+        );
+        let status = &CurrentTransactionStatuses::<Test>::get().unwrap()[0];
+        assert_eq!(status.to, None);
+        assert_eq!(status.contract_address, Some(address.into()));
+        System::<Test>::assert_has_event(RuntimeEvent::Ethink(
+            pallet_ethink::Event::ContractCreated {
+                from: ALITH.into(),
+                address: address.into(),
+                tx_hash,
+                transaction_index: 0,
+                gas_used: 300.into(),
+                storage_deposit: Default::default(),
+            },
+        ));
+    });
+}
+
+#[test]
+fn failed_execution_is_charged_for_gas_used() {
+    use crate::{CurrentReceipts, ExecError, Receipt};
+    use frame_support::traits::Hooks;
+
+    let contract = AccountId20([0x11; 20]);
+    MockContracts::mutate(|contracts| {
+        contracts.insert(
+            contract,
+            Err(ExecError {
+                error: pallet_contracts::Error::<Test>::ContractTrapped.into(),
+                gas_used: 700.into(),
+            }),
+        )
+    });
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Call(contract.into()),
+            gas_price: 1,
+            gas_limit: Weight::from_parts(1_000, 0),
+            data: vec![].into(),
+            ..Default::default()
+        });
+        let tx_hash = eth_tx.hash();
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        Ethink::on_finalize(1);
+
+        let nonce: u64 = System::<Test>::account_nonce(ALITH).into();
+        assert_eq!(nonce, 1);
+        assert_eq!(test_utils::get_balance(&ALITH), 10_000_000_000 - 700);
+        let Receipt::Legacy(receipt) = &CurrentReceipts::<Test>::get().unwrap()[0] else {
+            panic!("legacy transaction should have legacy receipt");
+        };
+        assert_eq!(receipt.status_code, 0);
+        assert_eq!(receipt.used_gas, U256::from(700));
+        // Failure reason is reported in the event
+        System::<Test>::assert_has_event(RuntimeEvent::Ethink(pallet_ethink::Event::TxFailed {
+            from: ALITH.into(),
+            to: Some(contract.into()),
+            tx_hash,
+            transaction_index: 0,
+            gas_used: 700.into(),
+            reason: pallet_contracts::Error::<Test>::ContractTrapped.into(),
+            revert_data: vec![],
+        }));
+    });
+}
+
+#[test]
+fn code_of_contract_is_returned_as_uploaded() {
+    let wasm = wat::parse_str(NOOP_CONTRACT_CODE).unwrap();
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(NOOP_CONTRACT_CODE, 0);
+
+        assert_eq!(Ethink::code_at(contract_addr), Some(wasm.clone()));
+        assert_eq!(
+            Ethink::code_hash_at(contract_addr),
+            Some(H256(sp_io::hashing::blake2_256(&wasm)))
+        );
+        // Account which is not a contract has no code
+        assert_eq!(Ethink::code_at(BALTATHAR), None);
+        assert_eq!(Ethink::code_hash_at(BALTATHAR), None);
+    });
+}
+
+#[test]
+fn contract_storage_is_read_by_evm_slot() {
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(STORAGE_CONTRACT_CODE, 0);
+
+        // Slot which fits into u32 is ink! storage key
+        assert_eq!(Ethink::storage_key(U256::from(7)), vec![7, 0, 0, 0]);
+        assert_eq!(
+            Ethink::storage_at(contract_addr, U256::zero()),
+            Some(vec![0x2a])
+        );
+        // Larger slot is passed through as a raw key
+        assert_eq!(
+            Ethink::storage_at(contract_addr, U256::MAX),
+            Some(vec![0x2b])
+        );
+        // Entries of ink! `Mapping` are not addressable by slot,
+        // hence the slot of the mapping root key reads as empty, not as some entry
+        assert_eq!(Ethink::storage_at(contract_addr, U256::one()), None);
+        // Nothing is stored in other slots
+        assert_eq!(Ethink::storage_at(contract_addr, U256::from(2)), None);
+        assert_eq!(Ethink::storage_at(BALTATHAR, U256::zero()), None);
+    });
+}
+
+#[test]
+fn state_overrides_are_applied_and_rolled_back() {
+    let wasm = wat::parse_str(STORAGE_CONTRACT_CODE).unwrap();
+    let new_wasm = wat::parse_str(REVERT_CONTRACT_CODE).unwrap();
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(STORAGE_CONTRACT_CODE, 0);
+        let slot = |n: u64| H256::from_low_u64_be(n);
+
+        // Balance, nonce and individual slots
+        let overrides = vec![
+            (
+                BALTATHAR.into(),
+                StateOverride {
+                    balance: Some(U256::from(10_000_000_000u64)),
+                    nonce: Some(U256::from(5)),
+                    ..Default::default()
+                },
+            ),
+            (
+                contract_addr.into(),
+                StateOverride {
+                    state_diff: Some(
+                        [(slot(1), slot(0x0102)), (H256::repeat_byte(0xff), slot(0))].into(),
+                    ),
+                    ..Default::default()
+                },
+            ),
+        ];
+        assert_ok!(Ethink::with_overrides(overrides, || {
+            assert_eq!(test_utils::get_balance(&BALTATHAR), 10_000_000_000);
+            assert_eq!(Ethink::account_nonce(&BALTATHAR.into()), U256::from(5));
+            // Value is stored with leading zeros stripped, zero value empties the slot
+            assert_eq!(
+                Ethink::storage_at(contract_addr, U256::one()),
+                Some(vec![1, 2])
+            );
+            assert_eq!(Ethink::storage_at(contract_addr, U256::MAX), None);
+            // Slots not overridden are kept
+            assert_eq!(
+                Ethink::storage_at(contract_addr, U256::zero()),
+                Some(vec![0x2a])
+            );
+            Ok(())
+        }));
+
+        // Code alone, with its upload paid for by neither the caller nor the contract
+        let overrides = vec![(
+            contract_addr.into(),
+            StateOverride {
+                code: Some(new_wasm.clone()),
+                ..Default::default()
+            },
+        )];
+        let (alith_balance, contract_balance) = (
+            test_utils::get_balance(&ALITH),
+            test_utils::get_balance(&contract_addr),
+        );
+        assert_ok!(Ethink::with_overrides(overrides, || {
+            assert_eq!(Ethink::code_at(contract_addr), Some(new_wasm.clone()));
+            assert_eq!(test_utils::get_balance(&ALITH), alith_balance);
+            assert_eq!(test_utils::get_balance(&contract_addr), contract_balance);
+            // Storage is kept
+            assert_eq!(
+                Ethink::storage_at(contract_addr, U256::zero()),
+                Some(vec![0x2a])
+            );
+            Ok(())
+        }));
+
+        // Whole storage and code
+        let overrides = vec![(
+            contract_addr.into(),
+            StateOverride {
+                balance: Some(U256::from(10_000_000_000u64)),
+                code: Some(new_wasm.clone()),
+                state: Some([(slot(1), slot(7))].into()),
+                ..Default::default()
+            },
+        )];
+        assert_ok!(Ethink::with_overrides(overrides, || {
+            assert_eq!(Ethink::code_at(contract_addr), Some(new_wasm.clone()));
+            assert_eq!(
+                Ethink::storage_at(contract_addr, U256::one()),
+                Some(vec![7])
+            );
+            assert_eq!(Ethink::storage_at(contract_addr, U256::zero()), None);
+            assert_eq!(Ethink::storage_at(contract_addr, U256::MAX), None);
+            Ok(())
+        }));
+
+        // Everything is rolled back afterwards
+        assert_eq!(test_utils::get_balance(&BALTATHAR), 0);
+        assert_eq!(Ethink::account_nonce(&BALTATHAR.into()), U256::zero());
+        assert_eq!(Ethink::code_at(contract_addr), Some(wasm));
+        assert_eq!(
+            Ethink::storage_at(contract_addr, U256::zero()),
+            Some(vec![0x2a])
+        );
+        assert_eq!(Ethink::storage_at(contract_addr, U256::one()), None);
+        assert_eq!(
+            Ethink::storage_at(contract_addr, U256::MAX),
+            Some(vec![0x2b])
+        );
+
+        // Storage of an account which is not a contract can't be overridden
+        let overrides = vec![(
+            BALTATHAR.into(),
+            StateOverride {
+                state_diff: Some([(slot(1), slot(1))].into()),
+                ..Default::default()
+            },
+        )];
+        assert_noop!(
+            Ethink::with_overrides(overrides, || Ok(())),
+            pallet_contracts::Error::<Test>::ContractNotFound
+        );
+        // Neither can its code
+        let overrides = vec![(
+            BALTATHAR.into(),
+            StateOverride {
+                code: Some(new_wasm),
+                ..Default::default()
+            },
+        )];
+        assert_noop!(
+            Ethink::with_overrides(overrides, || Ok(())),
+            pallet_contracts::Error::<Test>::ContractNotFound
+        );
+    });
+}
+
+#[test]
+fn code_override_needs_no_funds() {
+    let new_wasm = wat::parse_str(REVERT_CONTRACT_CODE).unwrap();
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract_addr = test_utils::deploy_fixture(STORAGE_CONTRACT_CODE, 0);
+        // Simulations often come from the zero address, which has no funds
+        assert_eq!(test_utils::get_balance(&AccountId20([0; 20])), 0);
+        let issuance = pallet_balances::TotalIssuance::<Test>::get();
+
+        let overrides = vec![(
+            contract_addr.into(),
+            StateOverride {
+                code: Some(new_wasm.clone()),
+                ..Default::default()
+            },
+        )];
+        assert_ok!(Ethink::with_overrides(overrides, || {
+            assert_eq!(Ethink::code_at(contract_addr), Some(new_wasm.clone()));
+            Ok(())
+        }));
+        // Funds of the upload are rolled back
+        assert_eq!(pallet_balances::TotalIssuance::<Test>::get(), issuance);
+    });
+}
+
+#[test]
+fn gas_is_estimated_as_lowest_limit_call_passes_with() {
+    use crate::{DryRunError, ExecResult, ETH_BASE_GAS_FEE};
+
+    let contract = AccountId20([0x11; 20]);
+    let reverting = AccountId20([0x22; 20]);
+    MockContracts::mutate(|contracts| {
+        contracts.insert(
+            contract,
+            Ok(ExecResult {
+                gas_used: 300.into(),
+                gas_required: 800.into(),
+                ..Default::default()
+            }),
+        );
+        contracts.insert(
+            reverting,
+            Ok(ExecResult {
+                output: vec![0xde, 0xad],
+                gas_used: 300.into(),
+                reverted: true,
+                ..Default::default()
+            }),
+        );
+    });
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let estimate = |to: AccountId20, value: u128, gas_limit: u64| {
+            Ethink::gas_estimate(ALITH, Some(to), vec![], value, gas_limit.into())
+        };
+
+        // Call needs more gas than it spends
+        assert_eq!(estimate(contract, 0, 10_000), Ok(U256::from(800)));
+        // Estimation is capped by the gas limit given
+        assert_eq!(
+            estimate(contract, 0, 700),
+            Err(DryRunError::Failed(
+                pallet_contracts::Error::<Test>::OutOfGas.into()
+            ))
+        );
+        // Revert data is reported if the call reverts
+        assert_eq!(
+            estimate(reverting, 0, 10_000),
+            Err(DryRunError::Reverted(vec![0xde, 0xad]))
+        );
+        // Transfer to an account costs the base fee, provided the sender can afford it
+        assert_eq!(
+            estimate(BALTATHAR, ED, 10_000),
+            Ok(U256::from(ETH_BASE_GAS_FEE))
+        );
+        assert!(estimate(BALTATHAR, 20_000_000_000, 10_000).is_err());
+        // Dry runs leave no trace
+        assert_eq!(test_utils::get_balance(&BALTATHAR), 0);
+    });
+}
+
+#[test]
+fn gas_estimation_limits_storage_deposit_as_transaction_does() {
+    use crate::ExecResult;
+
+    let contract = AccountId20([0x11; 20]);
+    MockContracts::mutate(|contracts| {
+        contracts.insert(
+            contract,
+            Ok(ExecResult {
+                gas_used: 300.into(),
+                gas_required: 800.into(),
+                ..Default::default()
+            }),
+        )
+    });
+    DepositLimitByFeeBudget::set(true);
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        assert_eq!(
+            Ethink::gas_estimate(ALITH, Some(contract), vec![], 0, 10_000.into()),
+            Ok(U256::from(800))
+        );
+
+        // Every attempt has the deposit limited by the fee budget of its gas limit,
+        // at the minimum gas price of a single unit of native balance
+        let calls = ExecutorCalls::get();
+        assert!(!calls.is_empty());
+        for call in calls {
+            let ExecutorCall::Call {
+                gas_limit,
+                storage_deposit_limit,
+                ..
+            } = call
+            else {
+                panic!("unexpected instantiation");
+            };
+            assert_eq!(storage_deposit_limit, Some(gas_limit.as_u128()));
+        }
+    });
+}
+
+#[test]
+fn gas_estimation_of_real_contract() {
+    use crate::DryRunError;
+
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 10_000_000_000);
+        let contract = test_utils::deploy_fixture(STORAGE_CONTRACT_CODE, 0);
+        let reverting = test_utils::deploy_fixture(REVERT_CONTRACT_CODE, 0);
+        let gas_limit = Ethink::block_gas_limit();
+
+        // Estimated gas is enough for the call to pass
+        let gas = Ethink::gas_estimate(ALITH, Some(contract), vec![], 0, gas_limit).unwrap();
+        assert!(gas < gas_limit);
+        assert!(Ethink::contract_call(ALITH, contract, vec![], 0, gas).is_ok());
+
+        assert_eq!(
+            Ethink::gas_estimate(ALITH, Some(reverting), vec![], 0, gas_limit),
+            Err(DryRunError::Reverted(vec![0xde, 0xad, 0xbe, 0xef]))
+        );
+
+        // Contract instantiation is estimated without the destination
+        let wasm = wat::parse_str(STORAGE_CONTRACT_CODE).unwrap();
+        let gas = Ethink::gas_estimate(ALITH, None, wasm.encode(), 0, gas_limit).unwrap();
+        assert!(gas < gas_limit);
+        // and the estimate is enough for the contract to be created
+        let eth_tx = compose_and_sign_tx(EthTxInput {
+            action: TransactionAction::Create,
+            gas_limit: Weight::from_parts(gas.as_u64(), 0),
+            data: wasm.encode().into(),
+            ..Default::default()
+        });
+        let origin = RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+        assert_ok!(Ethink::transact(origin, eth_tx));
+        assert!(System::<Test>::events().into_iter().any(|r| matches!(
+            r.event,
+            RuntimeEvent::Ethink(pallet_ethink::Event::ContractCreated { .. })
+        )));
+    });
+}
+
+#[test]
+fn storage_deposit_is_limited_by_fee_budget() {
+    use crate::{CurrentTransactionStatuses, StorageDeposit};
+    use frame_support::traits::Hooks;
+
+    let wasm = wat::parse_str(STORAGE_CONTRACT_CODE).unwrap();
+    DepositLimitByFeeBudget::set(true);
+    ExtBuilder::default().build().execute_with(|| {
+        let _ = test_utils::set_balance(&ALITH, 100_000_000_000);
+        let deploy = |nonce, gas_price| {
+            let eth_tx = compose_and_sign_tx(EthTxInput {
+                nonce,
+                action: TransactionAction::Create,
+                gas_price,
+                gas_limit: Weight::from_parts(20_000_000_000, 0),
+                data: wasm.encode().into(),
+                ..Default::default()
+            });
+            let origin =
+                RuntimeOrigin::from(pallet_ethink::RawOrigin::EthTransaction(ALITH.into()));
+            assert_ok!(Ethink::transact(origin, eth_tx));
+        };
+
+        // Free transaction can't take any storage deposit
+        deploy(1, 0);
+        // Transaction with enough fee budget pays the deposit
+        deploy(2, 1);
+        Ethink::on_finalize(1);
+
+        let statuses = CurrentTransactionStatuses::<Test>::get().unwrap();
+        assert_eq!(statuses[0].contract_address, None);
+        assert_eq!(statuses[0].storage_deposit, StorageDeposit::default());
+        let address = statuses[1]
+            .contract_address
+            .expect("contract should be created");
+        assert!(Contracts::code_hash(&address.into()).is_some());
+        assert!(matches!(statuses[1].storage_deposit, StorageDeposit::Charge(d) if !d.is_zero()));
+    });
+}
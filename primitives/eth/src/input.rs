@@ -1,7 +1,10 @@
-use crate::{EthTransaction, EthereumSignature, LegacyTransaction, LegacyTransactionMessage};
+use crate::{
+    EIP1559Transaction, EIP1559TransactionMessage, EIP2930Transaction, EIP2930TransactionMessage,
+    EthTransaction, EthereumSignature, LegacyTransaction, LegacyTransactionMessage,
+};
 use frame_support::weights::Weight;
 use serde::{Serialize, Serializer};
-use sp_core::{ecdsa, Pair, U256};
+use sp_core::{ecdsa, Pair, H256, U256};
 
 #[derive(Clone)]
 pub struct ContractInput(Vec<u8>);
@@ -77,12 +80,57 @@ impl From<EthTxInput> for LegacyTransactionMessage {
     }
 }
 
+impl From<EthTxInput> for EIP2930TransactionMessage {
+    fn from(v: EthTxInput) -> Self {
+        Self {
+            chain_id: v.chain_id.unwrap_or_default(),
+            nonce: v.nonce.into(),
+            gas_price: v.gas_price.into(),
+            gas_limit: v.gas_limit.ref_time().into(),
+            action: v.action,
+            value: v.value.into(),
+            input: v.data.into(),
+            access_list: vec![],
+        }
+    }
+}
+
+impl From<EthTxInput> for EIP1559TransactionMessage {
+    fn from(v: EthTxInput) -> Self {
+        Self {
+            chain_id: v.chain_id.unwrap_or_default(),
+            nonce: v.nonce.into(),
+            // gas_price is used both as a tip and as a fee cap
+            max_priority_fee_per_gas: v.gas_price.into(),
+            max_fee_per_gas: v.gas_price.into(),
+            gas_limit: v.gas_limit.ref_time().into(),
+            action: v.action,
+            value: v.value.into(),
+            input: v.data.into(),
+            access_list: vec![],
+        }
+    }
+}
+
+/// Sign typed tx message hash, return (odd_y_parity, r, s) of the signature
+fn sign_typed(signer: &ecdsa::Pair, hash: H256) -> (bool, H256, H256) {
+    let sig = signer.sign_prehashed(&hash.into());
+    let sig: &[u8] = sig.as_ref();
+
+    (
+        sig[64] != 0,
+        H256::from_slice(&sig[0..32]),
+        H256::from_slice(&sig[32..64]),
+    )
+}
+
 /// Build Eth tx message, sign it and build an Eth transaction
 pub fn compose_and_sign_tx(i: EthTxInput) -> EthTransaction {
     let msg: LegacyTransactionMessage = i.clone().into();
     let sig = EthereumSignature::new(i.signer.sign_prehashed(&msg.hash().into()));
-    let sig: Option<ethereum::TransactionSignature> = sig.into();
-    let signature = sig.expect("signer generated no signature");
+    let signature = sig
+        .into_tx_signature(msg.chain_id)
+        .expect("signer generated no signature");
 
     EthTransaction::Legacy(LegacyTransaction {
         nonce: msg.nonce,
@@ -94,3 +142,44 @@ pub fn compose_and_sign_tx(i: EthTxInput) -> EthTransaction {
         signature,
     })
 }
+
+/// Build EIP-2930 tx message, sign it and build an Eth transaction
+pub fn compose_and_sign_eip2930_tx(i: EthTxInput) -> EthTransaction {
+    let msg: EIP2930TransactionMessage = i.clone().into();
+    let (odd_y_parity, r, s) = sign_typed(&i.signer, msg.hash());
+
+    EthTransaction::EIP2930(EIP2930Transaction {
+        chain_id: msg.chain_id,
+        nonce: msg.nonce,
+        gas_price: msg.gas_price,
+        gas_limit: msg.gas_limit,
+        action: msg.action,
+        value: msg.value,
+        input: msg.input,
+        access_list: msg.access_list,
+        odd_y_parity,
+        r,
+        s,
+    })
+}
+
+/// Build EIP-1559 tx message, sign it and build an Eth transaction
+pub fn compose_and_sign_eip1559_tx(i: EthTxInput) -> EthTransaction {
+    let msg: EIP1559TransactionMessage = i.clone().into();
+    let (odd_y_parity, r, s) = sign_typed(&i.signer, msg.hash());
+
+    EthTransaction::EIP1559(EIP1559Transaction {
+        chain_id: msg.chain_id,
+        nonce: msg.nonce,
+        max_priority_fee_per_gas: msg.max_priority_fee_per_gas,
+        max_fee_per_gas: msg.max_fee_per_gas,
+        gas_limit: msg.gas_limit,
+        action: msg.action,
+        value: msg.value,
+        input: msg.input,
+        access_list: msg.access_list,
+        odd_y_parity,
+        r,
+        s,
+    })
+}
@@ -2,20 +2,27 @@
 
 mod account;
 mod signing;
+mod status;
 
 #[cfg(any(feature = "std", test))]
 mod input;
 
 #[cfg(any(feature = "std", test))]
-pub use input::{compose_and_sign_tx, ContractInput, EthTxInput};
+pub use input::{
+    compose_and_sign_eip1559_tx, compose_and_sign_eip2930_tx, compose_and_sign_tx, ContractInput,
+    EthTxInput,
+};
 
 pub use account::AccountId20;
-pub use signing::{EthereumSignature, EthereumSigner};
+pub use signing::{recover_signer, EthereumSignature, EthereumSigner};
+pub use status::{StorageDeposit, TransactionStatus};
 
 pub use ethereum::{
-    AccessListItem, BlockV2 as Block, EnvelopedDecodable, EnvelopedEncodable, LegacyTransaction,
-    LegacyTransactionMessage, Log, ReceiptV3 as Receipt, TransactionAction, TransactionSignature,
+    util::ordered_trie_root, AccessListItem, BlockV2 as Block, EIP1559Transaction,
+    EIP1559TransactionMessage, EIP2930Transaction, EIP2930TransactionMessage, EIP658ReceiptData,
+    EnvelopedDecodable, EnvelopedEncodable, LegacyTransaction, LegacyTransactionMessage, Log,
+    PartialHeader, ReceiptV3 as Receipt, TransactionAction, TransactionSignature,
     TransactionV2 as EthTransaction,
 };
 
-pub use ethereum_types::{H160, H256, H64, U256, U64};
+pub use ethereum_types::{Bloom, BloomInput, H160, H256, H64, U256, U64};
@@ -16,7 +16,10 @@
 // limitations under the License.
 
 //! Ethereum signing facilities
-use ethereum::TransactionSignature;
+use ethereum::{
+    EIP1559TransactionMessage, EIP2930TransactionMessage, LegacyTransactionMessage,
+    TransactionSignature, TransactionV2 as EthTransaction,
+};
 use ethereum_types::{H160, H256};
 use scale_codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
@@ -83,6 +86,14 @@ impl EthereumSignature {
         Self::new(ecdsa::Signature::from_raw(d))
     }
 
+    /// Convert to Ethereum transaction signature,
+    /// encoding EIP-155 `chain_id` into its `v` (if provided).
+    pub fn into_tx_signature(self, chain_id: Option<u64>) -> Option<TransactionSignature> {
+        let (v, r, s) = self.to_vrs(chain_id);
+
+        TransactionSignature::new(v, r, s)
+    }
+
     fn to_vrs(&self, chain_id: Option<u64>) -> (u64, H256, H256) {
         // Some Ethereum-specific signature magic
         let v = match chain_id {
@@ -98,9 +109,7 @@ impl EthereumSignature {
 
 impl From<EthereumSignature> for Option<TransactionSignature> {
     fn from(s: EthereumSignature) -> Self {
-        let (v, r, s) = s.to_vrs(None);
-
-        TransactionSignature::new(v, r, s)
+        s.into_tx_signature(None)
     }
 }
 
@@ -152,3 +161,32 @@ impl From<ecdsa::Public> for EthereumSigner {
         EthereumSigner(account.into())
     }
 }
+
+/// Recover the address of the account which signed the Ethereum transaction.
+pub fn recover_signer(tx: &EthTransaction) -> Option<H160> {
+    let mut sig = [0u8; 65];
+    let mut msg = [0u8; 32];
+    match tx {
+        EthTransaction::Legacy(t) => {
+            sig[0..32].copy_from_slice(&t.signature.r()[..]);
+            sig[32..64].copy_from_slice(&t.signature.s()[..]);
+            sig[64] = t.signature.standard_v();
+            msg.copy_from_slice(&LegacyTransactionMessage::from(t.clone()).hash()[..]);
+        }
+        EthTransaction::EIP2930(t) => {
+            sig[0..32].copy_from_slice(&t.r[..]);
+            sig[32..64].copy_from_slice(&t.s[..]);
+            sig[64] = t.odd_y_parity as u8;
+            msg.copy_from_slice(&EIP2930TransactionMessage::from(t.clone()).hash()[..]);
+        }
+        EthTransaction::EIP1559(t) => {
+            sig[0..32].copy_from_slice(&t.r[..]);
+            sig[32..64].copy_from_slice(&t.s[..]);
+            sig[64] = t.odd_y_parity as u8;
+            msg.copy_from_slice(&EIP1559TransactionMessage::from(t.clone()).hash()[..]);
+        }
+    }
+    sp_io::crypto::secp256k1_ecdsa_recover(&sig, &msg)
+        .ok()
+        .map(|p| H160::from(H256::from(keccak_256(&p))))
+}
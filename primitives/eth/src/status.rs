@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: Apache-2.0
+// This file was derived from Frontier (fp-rpc),
+// and modified to become part of Ethink.
+//
+// Copyright (c) (Frontier): 2020-2022 Parity Technologies (UK) Ltd.
+// Copyright (c) (Ethink):   2023-2024 Alexander Gryaznov.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum transaction execution status
+use ethereum::Log;
+use ethereum_types::{Bloom, H160, H256, U256};
+use scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_std::vec::Vec;
+
+/// Outcome of an Ethereum transaction executed in a block,
+/// as needed by the RPC to serve transactions and receipts.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct TransactionStatus {
+    /// Hash of the transaction
+    pub transaction_hash: H256,
+    /// Index of the transaction among the Ethereum transactions of the block
+    pub transaction_index: u32,
+    /// Sender
+    pub from: H160,
+    /// Recipient, `None` for contract creation
+    pub to: Option<H160>,
+    /// Address of the contract created by the transaction, if any
+    pub contract_address: Option<H160>,
+    /// Logs emitted during transaction execution
+    pub logs: Vec<Log>,
+    /// Bloom filter of the logs
+    pub logs_bloom: Bloom,
+    /// Data returned by the contract which reverted the transaction,
+    /// empty for successful transactions
+    pub revert_data: Vec<u8>,
+    /// Storage deposit charged from, or refunded to the sender
+    pub storage_deposit: StorageDeposit,
+}
+
+/// Storage deposit charged from, or refunded to the sender upon transaction execution, in wei.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Encode, Decode, TypeInfo)]
+pub enum StorageDeposit {
+    /// Deposit reserved for the storage taken by the transaction
+    Charge(U256),
+    /// Deposit released for the storage freed by the transaction
+    Refund(U256),
+}
+
+impl Default for StorageDeposit {
+    fn default() -> Self {
+        StorageDeposit::Charge(U256::zero())
+    }
+}
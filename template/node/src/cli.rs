@@ -7,6 +7,11 @@ pub struct Cli {
 
     #[clap(flatten)]
     pub run: RunCmd,
+
+    /// Keep the mapping of Ethereum transactions and blocks in memory,
+    /// instead of the database under the base path. It is rebuilt on every start then.
+    #[arg(long)]
+    pub eth_mapping_in_memory: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
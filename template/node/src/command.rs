@@ -183,8 +183,9 @@ pub fn run() -> sc_cli::Result<()> {
         }
         None => {
             let runner = cli.create_runner(&cli.run)?;
+            let eth_mapping_in_memory = cli.eth_mapping_in_memory;
             runner.run_node_until_exit(|config| async move {
-                service::new_full::<sc_network::NetworkWorker<_, _>>(config)
+                service::new_full::<sc_network::NetworkWorker<_, _>>(config, eth_mapping_in_memory)
                     .map_err(sc_cli::Error::Service)
             })
         }
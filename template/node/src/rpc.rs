@@ -7,7 +7,7 @@
 
 use std::sync::Arc;
 
-use ethink_rpc::{EthApiServer, EthRPC};
+use ethink_rpc::{EthApiServer, EthRPC, MappingDb};
 use ethink_runtime::{opaque::Block, AccountId, Balance, Nonce};
 use jsonrpsee::RpcModule;
 use pallet_ethink::EthinkAPI;
@@ -33,6 +33,8 @@ pub struct FullDeps<C, P> {
     pub keystore: Arc<dyn Keystore>,
     /// Network syncing service
     pub sync: Arc<SyncingService<Block>>,
+    /// Mapping of Ethereum transactions and blocks to Substrate blocks
+    pub eth_mapping: Arc<MappingDb<Block>>,
 }
 
 /// Instantiate all full RPC extensions.
@@ -62,13 +64,24 @@ where
         deny_unsafe,
         keystore,
         sync,
+        eth_mapping,
     } = deps;
 
     module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
     module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
 
     // Eth RPC
-    module.merge(EthRPC::new(client.clone(), pool, keystore, sync).into_rpc())?;
+    module.merge(
+        EthRPC::new(
+            client.clone(),
+            pool,
+            keystore,
+            sync,
+            eth_mapping,
+            crate::service::eth_transaction,
+        )
+        .into_rpc(),
+    )?;
 
     // Extend this RPC with a custom API by using the following syntax.
     // `YourRpcStruct` should have a reference to a client, which is needed
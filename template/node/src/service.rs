@@ -1,7 +1,11 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use ethink_rpc::{
+    mapping::KeyValueStore, InMemoryStore, MappingDb, MappingSyncWorker, ParityDbStore,
+};
 use ethink_runtime::{self, opaque::Block, RuntimeApi};
 use futures::FutureExt;
+use pallet_ethink::EthTransaction;
 use sc_client_api::{Backend, BlockBackend};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
 use sc_consensus_grandpa::SharedVoterState;
@@ -9,6 +13,7 @@ pub use sc_executor::NativeElseWasmExecutor;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpSyncParams};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
+use scale_codec::{Decode, Encode};
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
 use sp_runtime::traits::Block as BlockT;
 use std::{sync::Arc, time::Duration};
@@ -147,8 +152,20 @@ pub fn new_partial(
     })
 }
 
+/// Ethereum transaction of the extrinsic, provided it is an `Ethink::transact` call.
+pub(crate) fn eth_transaction(xt: &<Block as BlockT>::Extrinsic) -> Option<EthTransaction> {
+    let xt = ethink_runtime::UncheckedExtrinsic::decode(&mut &xt.encode()[..]).ok()?;
+    match xt.0.function {
+        ethink_runtime::RuntimeCall::Ethink(pallet_ethink::Call::transact { tx }) => Some(tx),
+        _ => None,
+    }
+}
+
 /// Builds a new service for a full client.
-pub fn new_full<N>(config: Configuration) -> Result<TaskManager, ServiceError>
+pub fn new_full<N>(
+    config: Configuration,
+    eth_mapping_in_memory: bool,
+) -> Result<TaskManager, ServiceError>
 where
     N: sc_network::NetworkBackend<Block, <Block as BlockT>::Hash>,
 {
@@ -226,6 +243,26 @@ where
         );
     }
 
+    // Mapping of Ethereum transactions and blocks, kept in sync with the chain by the worker
+    let eth_mapping_store: Arc<dyn KeyValueStore> = if eth_mapping_in_memory {
+        Arc::new(InMemoryStore::default())
+    } else {
+        let path = config
+            .base_path
+            .config_dir(config.chain_spec.id())
+            .join("ethink")
+            .join("mapping");
+        Arc::new(ParityDbStore::open(&path).map_err(ServiceError::Other)?)
+    };
+    let eth_mapping = Arc::new(MappingDb::<Block>::new(eth_mapping_store));
+    // Syncing makes blocking client calls for every block on the way, e.g. upon catching up
+    // with the chain history, hence it gets a thread of its own
+    task_manager.spawn_handle().spawn_blocking(
+        "ethink-mapping-sync-worker",
+        Some("ethink"),
+        MappingSyncWorker::new(client.clone(), backend.clone(), eth_mapping.clone()).run(),
+    );
+
     let role = config.role.clone();
     let force_authoring = config.force_authoring;
     let backoff_authoring_blocks: Option<()> = None;
@@ -245,6 +282,7 @@ where
                 pool: pool.clone(),
                 keystore: keystore.clone(),
                 sync: sync.clone(),
+                eth_mapping: eth_mapping.clone(),
                 deny_unsafe,
             };
             crate::rpc::create_full(deps).map_err(Into::into)
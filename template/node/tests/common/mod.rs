@@ -42,6 +42,8 @@ pub mod consts {
     pub const BALTATHAR: Address = address!("3Cd0A705a2DC65e5b1E1205896BaA2be8A07c6e0");
     pub const BALTATHAR_KEY: &'static str =
         "0x8075991ce870b93a8870eca0c0f91913d12f47948ca0fd25b49c6fa7cdbeee8b";
+    // Gas limit for transactions, which should fit into the block weight limit
+    pub const GAS_LIMIT: u64 = 1_000_000_000_000;
 }
 
 #[derive(Clone)]
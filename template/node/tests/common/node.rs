@@ -120,7 +120,8 @@ where
             .stderr(process::Stdio::piped())
             .arg("--dev")
             .arg("--port=0")
-            .arg("--rpc-port=0");
+            .arg("--rpc-port=0")
+            .arg("--eth-mapping-in-memory");
 
         let mut proc = cmd.spawn().map_err(|e| {
             format!(
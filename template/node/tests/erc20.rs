@@ -20,15 +20,17 @@
 #![allow(non_snake_case)]
 use alloy::{
     network::EthereumWallet,
-    primitives::U256,
+    primitives::{Bytes, U256},
     providers::{Provider, ProviderBuilder},
     signers::local::PrivateKeySigner,
+    sol_types::{Revert, SolCall, SolError},
 };
+use serde_json::Deserializer;
 use std::sync::Once;
 
 use common::{codegen::*, consts::*, *};
 use ep_eth::{AccountId20, EnvelopedEncodable, EthTxInput, TransactionAction};
-use ethink_runtime::{Weight, ED};
+use ethink_runtime::{Weight, DECIMALS, ED};
 
 mod common;
 
@@ -67,8 +69,11 @@ async fn transfer_works() {
         .get_balance(env.contract_addr())
         .await
         .expect("can't get balance");
-    // Deployed contract should have ED balance
-    assert_eq!(contract_bal, U256::from(ED));
+    // Deployed contract should have ED balance, reported in wei
+    assert_eq!(
+        contract_bal,
+        U256::from(ED) * U256::from(10).pow(U256::from(18 - DECIMALS))
+    );
     // Get our ink! contract instance as Solidity contract
     let contract = IERC20::new(env.contract_addr(), rpc);
     // ETH RPC: query ERC20 token balances
@@ -84,20 +89,36 @@ async fn transfer_works() {
 
     // ETH RPC: send tx to transfer 100k of ERC20 to Alith
     // NOTE BALTATHAR's key is inserted into the node's keystore
-    let _tx_hash = contract
+    let tx_hash = *contract
         .transfer(ALITH, U256::from(100_000))
         .from(BALTATHAR)
-        .gas(u64::MAX)
+        .gas(GAS_LIMIT)
         .send()
         .await
-        .unwrap();
-    // TODO: make rpc return proper receipt, and wait for tx execution completeness like this:
-    //        .watch()
-    //        .await
-    //        .unwrap();
+        .unwrap()
+        .tx_hash();
 
     // Wait tx to be included into block
     let _ = &env.wait_for_event("Ethink.TxExecuted", 3).await;
+    // ETH RPC: the receipt reports the successful execution
+    let receipt = contract
+        .provider()
+        .get_transaction_receipt(tx_hash)
+        .await
+        .unwrap()
+        .expect("receipt is missing");
+    assert!(receipt.status());
+    assert_eq!(receipt.transaction_hash, tx_hash);
+    assert_eq!(receipt.from, BALTATHAR);
+    assert_eq!(receipt.to, Some(env.contract_addr()));
+    assert!(receipt.gas_used > 0);
+    // Unknown transaction has no receipt
+    let unknown = contract
+        .provider()
+        .get_transaction_receipt(Default::default())
+        .await
+        .unwrap();
+    assert!(unknown.is_none());
     // ETH RPC: query ERC20 token balances
     let (a_bal, b_bal) = (
         cal_a.call().await.unwrap()._0,
@@ -153,12 +174,12 @@ async fn approve_allowance_transfer_from_works() {
     let _tx_hash = contract
         .transfer(ALITH, U256::from(35_000))
         .from(ALITH)
-        .gas(u64::MAX)
+        .gas(GAS_LIMIT)
         .send()
         .await
         .unwrap();
     // Wait until tx fails
-    let _ = &env.wait_for_event("System.ExtrinsicFailed", 3).await;
+    let _ = &env.wait_for_event("Ethink.TxFailed", 3).await;
 
     // Balances should stay the same
     // ETH RPC: query ERC20 token balances
@@ -174,7 +195,7 @@ async fn approve_allowance_transfer_from_works() {
     let _tx_hash = contract_b
         .approve(ALITH, U256::from(100_000))
         .from(BALTATHAR)
-        .gas(u64::MAX)
+        .gas(GAS_LIMIT)
         .send()
         .await
         .unwrap();
@@ -190,7 +211,7 @@ async fn approve_allowance_transfer_from_works() {
     let _tx_hash = contract
         .transferFrom(BALTATHAR, ALITH, U256::from(35_000))
         .from(ALITH)
-        .gas(u64::MAX)
+        .gas(GAS_LIMIT)
         .send()
         .await
         .unwrap();
@@ -210,3 +231,45 @@ async fn approve_allowance_transfer_from_works() {
     // Remaining allowance should be
     assert_eq!(cal_allowance.call().await.unwrap()._0, U256::from(65_000));
 }
+
+#[tokio::test]
+async fn transfer_over_balance_reverts_with_reason() {
+    // SUBSTRATE RPC: Spawn node and deploy contract
+    let env: Env<PolkadotConfig> = prepare_node_and_contract!(
+        ONCE,
+        ERC20_PATH,
+        vec![&ERC20_SUPPLY.to_string()],
+        BALTATHAR_KEY
+    );
+    // ETH RPC: call transfer of 1 token from Alith, who has none
+    let data = IERC20::transferCall {
+        recipient: BALTATHAR,
+        amount: U256::from(1),
+    }
+    .abi_encode();
+    let rs = rpc_rq!(env,
+    {
+      "jsonrpc": "2.0",
+      "method": "eth_call",
+      "params": [{
+                  "from": ALITH_ADDRESS,
+                  "to": env.contract_addr(),
+                  "data": Bytes::from(data),
+                 },
+                 "latest"],
+      "id": 0
+    });
+    let json = to_json_val!(rs);
+    ensure_err!(&json, "transfer over balance should revert");
+    // Should be reported as execution error,
+    // with the revert reason encoded as Solidity `Error(string)`
+    let expected_data = Bytes::from(
+        Revert {
+            reason: "insufficient balance".into(),
+        }
+        .abi_encode(),
+    );
+    assert_eq!(json["error"]["code"], 3);
+    assert_eq!(json["error"]["message"], "execution reverted");
+    assert_eq!(json["error"]["data"], expected_data.to_string());
+}
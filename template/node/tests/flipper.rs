@@ -20,7 +20,7 @@
 #![allow(non_snake_case)]
 use alloy::providers::ProviderBuilder;
 use ep_eth::{compose_and_sign_tx, AccountId20, EnvelopedEncodable, EthTxInput, TransactionAction};
-use ethink_runtime::Weight;
+use ethink_runtime::{Weight, CHAIN_ID, DECIMALS, GAS_PER_PROOF_BYTE, WEIGHT_PER_GAS};
 use serde_json::{value::Serializer, Deserializer};
 use sp_core::{ecdsa, Pair, U256};
 use sp_runtime::Serialize;
@@ -48,6 +48,10 @@ async fn eth_sendRawTransaction() {
         signer: ecdsa::Pair::from_string(ALITH_KEY, None).unwrap(),
         action: TransactionAction::Call(env.contract_address().into()),
         data: encode!(FLIPPER_PATH, "flip"),
+        gas_limit: Weight::from_parts(GAS_LIMIT, 0),
+        // Minimum gas price: a single unit of native balance per gas
+        gas_price: 10u64.pow(18 - DECIMALS),
+        chain_id: Some(CHAIN_ID),
         ..Default::default()
     };
     let tx = compose_and_sign_tx(input);
@@ -65,7 +69,7 @@ async fn eth_sendRawTransaction() {
     ensure_no_err!(&json);
     let _tx_hash = extract_result!(&json);
     // Wait until tx gets executed
-    let _ = &env.wait_for_event("ethink.EthTransactionExecuted", 3).await;
+    let _ = &env.wait_for_event("Ethink.TxExecuted", 3).await;
     // Check state
     // SUBSTRATE RPC: make rq with cargo-contract
     let output = call!(env, "get");
@@ -104,7 +108,7 @@ async fn eth_sendTransaction() {
                   "from": BALTATHAR_ADDRESS,
                   "to": &env.contract_address(),
                   "data": encode!(FLIPPER_PATH, "flip"),
-                  "gas": U256::from(u64::MAX)
+                  "gas": U256::from(GAS_LIMIT)
                  },
                  "latest"],
       "id": 0
@@ -115,7 +119,7 @@ async fn eth_sendTransaction() {
     ensure_no_err!(&json);
     let _tx_hash = extract_result!(&json);
     // Wait until tx gets executed
-    let _ = &env.wait_for_event("ethink.EthTransactionExecuted", 3).await;
+    let _ = &env.wait_for_event("Ethink.TxExecuted", 3).await;
     // Check state
     let output = call!(env, "get");
     let rs = Deserializer::from_slice(&output.stdout);
@@ -132,7 +136,7 @@ async fn gas_limit_is_respected() {
         prepare_node_and_contract!(ONCE, FLIPPER_PATH, vec!["false"], BALTATHAR_KEY);
     // (Flipper is deployed with `false` state)
     // Make ETH RPC request (to flip it to `true`)
-    // Insufficient gas_limit (None => 0)
+    // Insufficient gas_limit (base gas of a plain transfer)
     let rs = rpc_rq!(env,
     {
       "jsonrpc": "2.0",
@@ -140,7 +144,8 @@ async fn gas_limit_is_respected() {
       "params": [{
                   "from": BALTATHAR_ADDRESS,
                   "to": &env.contract_address(),
-                  "data": encode!(FLIPPER_PATH, "flip")
+                  "data": encode!(FLIPPER_PATH, "flip"),
+                  "gas": U256::from(21_000),
                  },
                  "latest"],
       "id": 0
@@ -150,7 +155,7 @@ async fn gas_limit_is_respected() {
     ensure_no_err!(&json);
     let _tx_hash = extract_result!(&json);
     // Wait until tx fails (or timeout)
-    let _ = &env.wait_for_event("system.ExtrinsicFailed", 2).await;
+    let _ = &env.wait_for_event("Ethink.TxFailed", 2).await;
     // Check state
     let output = call!(env, "get");
     let rs = Deserializer::from_slice(&output.stdout);
@@ -189,7 +194,7 @@ async fn gas_limit_is_respected() {
     ensure_no_err!(&json);
     let _tx_hash = extract_result!(&json);
     // Wait until tx fails (or timeout)
-    let _ = &env.wait_for_event("system.ExtrinsicFailed", 2).await;
+    let _ = &env.wait_for_event("Ethink.TxFailed", 2).await;
     // Check state
     let output = call!(env, "get");
     let rs = Deserializer::from_slice(&output.stdout);
@@ -213,7 +218,7 @@ async fn eth_call() {
                       "from": ALITH_ADDRESS,
                       "to": &env.contract_address(),
                       "data": encode!(FLIPPER_PATH, "get"),
-                      "gas": U256::from(u64::MAX)
+                      "gas": U256::from(GAS_LIMIT)
                   },
                   "latest"],
        "id": 0
@@ -238,18 +243,151 @@ async fn eth_call() {
     assert_eq!(*result, "0x01");
 }
 
+#[tokio::test]
+async fn eth_call_at_older_block() {
+    // Spawn node and deploy contract
+    let mut env: Env<PolkadotConfig> =
+        prepare_node_and_contract!(ONCE, FLIPPER_PATH, vec!["false"]);
+    // (Flipper is deployed with `false` state)
+    // Remember the block at which flipper is still `false`
+    let rs = rpc_rq!(env,
+    {
+      "jsonrpc": "2.0",
+      "method": "eth_getBlockByNumber",
+      "params": ["latest", false],
+      "id": 0
+    });
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    let number = json["result"]["number"].clone();
+    let hash = json["result"]["hash"].clone();
+    // Flip it via contract call
+    let _ = call!(env, "flip", vec![], true);
+    // Wait until tx gets executed
+    let _ = &env.wait_for_event("contracts.Called", 2).await;
+    // Compose eth_call rpc request to get() flipper state at the block given
+    let data = encode!(FLIPPER_PATH, "get");
+    let get_at = |block: serde_json::Value| {
+        json!({
+           "jsonrpc": "2.0",
+           "method": "eth_call",
+           "params": [{
+                          "from": ALITH_ADDRESS,
+                          "to": &env.contract_address(),
+                          "data": &data,
+                          "gas": U256::from(GAS_LIMIT)
+                      },
+                      block],
+           "id": 1
+        })
+    };
+    // Latest state is `true`
+    let rq = get_at(json!("latest"));
+    let rs = rpc_rq!(env, rq);
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    assert_eq!(*extract_result!(&json), "0x01");
+    // but it was `false` at the older block, requested by number
+    let rq = get_at(number);
+    let rs = rpc_rq!(env, rq);
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    assert_eq!(*extract_result!(&json), "0x00");
+    // as well as by EIP-1898 block hash
+    let rq = get_at(json!({ "blockHash": hash, "requireCanonical": true }));
+    let rs = rpc_rq!(env, rq);
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    assert_eq!(*extract_result!(&json), "0x00");
+}
+
+#[tokio::test]
+async fn eth_call_with_state_overrides() {
+    // Spawn node and deploy contract
+    let env: Env<PolkadotConfig> = prepare_node_and_contract!(ONCE, FLIPPER_PATH, vec!["false"]);
+    // Make ETH rpc request to get() flipper state, as if its root storage slot was `true`
+    let contract = env.contract_address().to_string();
+    let rq = json!({
+       "jsonrpc": "2.0",
+       "method": "eth_call",
+       "params": [{
+                      "from": ALITH_ADDRESS,
+                      "to": &env.contract_address(),
+                      "data": encode!(FLIPPER_PATH, "get"),
+                      "gas": U256::from(GAS_LIMIT)
+                  },
+                  "latest",
+                  {
+                      contract: {
+                          "stateDiff": {
+                              "0x0000000000000000000000000000000000000000000000000000000000000000":
+                              "0x0000000000000000000000000000000000000000000000000000000000000001"
+                          }
+                      }
+                  }],
+       "id": 0
+    });
+    let rs = rpc_rq!(env, rq);
+    // Handle response
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    assert_eq!(*extract_result!(&json), "0x01");
+    // Override is not persisted
+    let rq = json!({
+       "jsonrpc": "2.0",
+       "method": "eth_getStorageAt",
+       "params": [&env.contract_address(), "0x0", "latest"],
+       "id": 1
+    });
+    let rs = rpc_rq!(env, rq);
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    assert_eq!(
+        *extract_result!(&json),
+        "0x0000000000000000000000000000000000000000000000000000000000000000"
+    );
+}
+
+#[tokio::test]
+async fn eth_estimateGas_at_older_block() {
+    // Spawn node and deploy contract
+    let env: Env<PolkadotConfig> = prepare_node_and_contract!(ONCE, FLIPPER_PATH, vec!["false"]);
+    // Make ETH rpc request at genesis, where there is no contract deployed yet
+    let rq = json!({
+       "jsonrpc": "2.0",
+       "method": "eth_estimateGas",
+       "params": [{
+                      "from": ALITH_ADDRESS,
+                      "to": &env.contract_address(),
+                      "data": encode!(FLIPPER_PATH, "flip")
+                  },
+                  "earliest"],
+       "id": 0
+    });
+    let rs = rpc_rq!(env, rq);
+    // Handle response
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    // Should estimate a plain transfer then
+    let gas = extract_result!(&json);
+    assert_eq!(gas, "0x5208");
+}
+
 #[tokio::test]
 async fn eth_estimateGas() {
     // Spawn node and deploy contract
     let env: Env<PolkadotConfig> = prepare_node_and_contract!(ONCE, FLIPPER_PATH, vec!["false"]);
-    // Retrieve gas estimation via cargo-contract dry-run
+    // Retrieve gas consumed and required via cargo-contract dry-run
     let output = call!(env, "flip");
     let rs = Deserializer::from_slice(&output.stdout);
-    let gas_consumed = json_get!(rs["gas_consumed"]).to_owned();
-    let weight = serde_json::from_value::<Weight>(gas_consumed)
-        .map(|w| U256::from(w.ref_time()))
-        .unwrap();
-    let weight_str_expected = weight.serialize(Serializer).unwrap().to_owned();
+    let dry_run = to_json_val!(rs);
+    // Runtime maps weight to gas, so that it covers both weight components
+    let to_gas = |weight: serde_json::Value| {
+        let weight = serde_json::from_value::<Weight>(weight).unwrap();
+        U256::from(
+            (weight.ref_time() / WEIGHT_PER_GAS).max(weight.proof_size() * GAS_PER_PROOF_BYTE),
+        )
+    };
     // Make ETH rpc request
     let rq = json!({
        "jsonrpc": "2.0",
@@ -266,9 +404,11 @@ async fn eth_estimateGas() {
     // Handle response
     let json = to_json_val!(rs);
     ensure_no_err!(&json);
-    // Should return gas spent value equal to the one retrieved above
-    let weight_str_returned = extract_result!(&json);
-    assert_eq!(weight_str_returned, &weight_str_expected);
+    // Should return the lowest gas limit the call passes with,
+    // which is somewhere between the gas consumed and the gas required
+    let gas = U256::from_str_radix(extract_result!(&json).trim_start_matches("0x"), 16).unwrap();
+    assert!(gas >= to_gas(dry_run["gas_consumed"].clone()));
+    assert!(gas <= to_gas(dry_run["gas_required"].clone()));
 }
 
 #[tokio::test]
@@ -314,8 +454,9 @@ async fn eth_getBlockTransactionCountByNumber() {
       "params": [{
                   "from": BALTATHAR_ADDRESS,
                   "to": ALITH_ADDRESS,
-                  "value": "17500",
-                  "gas": U256::from(u64::MAX)
+                  // 10^12 wei, which is 10^6 native balance units
+                  "value": "0xe8d4a51000",
+                  "gas": U256::from(GAS_LIMIT)
                  },
                  "latest"],
       "id": 0
@@ -376,9 +517,36 @@ async fn eth_getCode() {
     // Handle response
     let json = to_json_val!(rs);
     ensure_no_err!(&json);
-    let code_hash = extract_result!(&json);
+    let code = extract_result!(&json);
+    let code = hex::decode(code.trim_start_matches("0x")).unwrap();
+    // Wasm blob of the contract should be returned, as it was uploaded
+    assert!(code.starts_with(b"\0asm"));
     assert_eq!(
-        *code_hash,
+        format!("0x{}", hex::encode(sp_core::hashing::blake2_256(&code))),
         "0x3cc300a47907064879ef61f981941535ccb988aaec1078b0221811c8fc96f1ff"
     )
 }
+
+#[tokio::test]
+async fn eth_getStorageAt() {
+    // Spawn node and deploy contract
+    let mut env: Env<PolkadotConfig> = prepare_node_and_contract!(ONCE, FLIPPER_PATH, vec!["true"]);
+    // (Flipper is deployed with `true` state)
+    // Make ETH RPC request for slot 0, which is the root storage of ink! contract
+    let rs = rpc_rq!(env,
+    {
+      "jsonrpc": "2.0",
+      "method": "eth_getStorageAt",
+      "params": [ env.contract_addr(), "0x0", "latest" ],
+      "id": 0
+     });
+    // Handle response
+    let json = to_json_val!(rs);
+    ensure_no_err!(&json);
+    let value = extract_result!(&json);
+    // Flipper state is stored as a single byte
+    assert_eq!(
+        value,
+        "0x0000000000000000000000000000000000000000000000000000000000000001"
+    )
+}
@@ -38,7 +38,7 @@ use frame_system::{
 };
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, U256};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256};
 use sp_genesis_builder::PresetId;
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
@@ -61,8 +61,8 @@ use pallet_ethink::EthTransaction;
 pub use frame_support::{
     construct_runtime, parameter_types,
     traits::{
-        AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU32, ConstU8, KeyOwnerProofSystem,
-        Randomness, StorageInfo,
+        AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU32, ConstU64, ConstU8,
+        KeyOwnerProofSystem, Randomness, StorageInfo,
     },
     weights::{
         constants::{
@@ -173,7 +173,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     //   `spec_version`, and `authoring_version` are the same between Wasm and native.
     // This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
     //   the compatible custom types.
-    spec_version: 100,
+    spec_version: 101,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
     transaction_version: 1,
@@ -199,6 +199,12 @@ pub const DAYS: BlockNumber = HOURS * 24;
 
 /// ETH specific constants
 pub const CHAIN_ID: u64 = 42;
+/// Ref time weight a unit of gas buys
+pub const WEIGHT_PER_GAS: u64 = 1;
+/// Units of gas a byte of proof size costs
+pub const GAS_PER_PROOF_BYTE: u64 = 4;
+/// Decimals of the native balance, see `UNIT`
+pub const DECIMALS: u32 = 12;
 
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
@@ -492,6 +498,20 @@ impl pallet_ethink::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
     type Contracts = Contracts;
+    type OnChargeTransaction = pallet_ethink::FungibleAdapter<Balances, ()>;
+    type LogMapping = pallet_ethink::RawLogs;
+    type GasWeightMapping = pallet_ethink::FixedGasWeightMapping<
+        ConstU64<WEIGHT_PER_GAS>,
+        ConstU64<GAS_PER_PROOF_BYTE>,
+    >;
+    type BalanceConverter = pallet_ethink::DecimalsConverter<ConstU32<DECIMALS>>;
+    // Storage deposits are way above fees at the minimum gas price,
+    // so limiting them by the fee budget would fail contract deployments.
+    // Instead, they are bounded the same way as contract calls without an explicit limit.
+    type DepositLimit = pallet_ethink::ConstDepositLimit<DefaultDepositLimit>;
+    type TransactionRetention = ConstU32<{ 7 * DAYS }>;
+    type ChainId = ConstU64<CHAIN_ID>;
+    type AllowUnprotectedTxs = ConstBool<false>;
     type Call = RuntimeCall;
     type WeightInfo = pallet_ethink::weights::SubstrateWeight<Self>;
 }
@@ -575,6 +595,37 @@ mod benches {
     frame_benchmarking::define_benchmarks!([pallet_ethink, Ethink]);
 }
 
+/// Dry run of the contract call coming from Ethereum RPC, returning the output of the contract.
+fn eth_call(
+    from: H160,
+    to: H160,
+    data: Vec<u8>,
+    value: U256,
+    gas_limit: U256,
+) -> Result<Vec<u8>, pallet_ethink::DryRunError> {
+    log::debug!(
+        "CALLING:\nfrom:{:?}\nto:{:?},\ndata:{:?},\ngas_limit:{:?}",
+        &from,
+        &to,
+        &data,
+        &gas_limit
+    );
+    let value = Ethink::wei_to_native(value).map_err(DispatchError::from)?;
+    let result = Ethink::contract_call(from.into(), to.into(), data, value, gas_limit)
+        .map_err(|e| e.error)?;
+    if result.reverted {
+        // Revert data is passed through as is, for the caller to decode the reason
+        log::debug!("Contract {:?} call reverted: {:x?}", &to, &result.output);
+        return Err(pallet_ethink::DryRunError::Reverted(result.output));
+    }
+    // NOTE: ink! returns returned val wrapped into Result, which takes 1st byte
+    // here we rm it to get the inner value only.
+    // Proper solution would be to upstream abi logic into ink!.
+    let dbg = result.output[1..].to_vec();
+    log::debug!("Contract returned val: {:x?}", &dbg);
+    Ok(dbg)
+}
+
 impl_runtime_apis! {
     impl sp_api::Core<Block> for Runtime {
         fn version() -> RuntimeVersion {
@@ -826,55 +877,86 @@ impl_runtime_apis! {
             Ethink::code_at(AccountId::from(address))
         }
 
+        fn code_hash_at(address: H160) -> Option<H256> {
+            Ethink::code_hash_at(AccountId::from(address))
+        }
+
+        fn storage_at(address: H160, slot: U256) -> Option<Vec<u8>> {
+            Ethink::storage_at(AccountId::from(address), slot)
+        }
+
         fn chain_id() -> u64 {
-            CHAIN_ID
+            Ethink::chain_id()
         }
 
         fn account_free_balance(address: H160) -> U256 {
-            Balances::free_balance(
-                &address.into(),
-            ).into()
+            Ethink::account_balance(&address)
         }
 
         fn nonce(address: H160) -> U256 {
-            System::account_nonce(AccountId::from(address)).into()
+            Ethink::account_nonce(&address)
+        }
+
+        fn block_gas_limit() -> U256 {
+            Ethink::block_gas_limit()
+        }
+
+        fn gas_price() -> U256 {
+            Ethink::min_gas_price()
         }
 
         fn call(
             from: H160,
             to: H160,
             data: Vec<u8>,
-            value: u128,
-            gas_limit: Weight,
-        ) -> Result<Vec<u8>, DispatchError> {
-            log::debug!("CALLING:\nfrom:{:?}\nto:{:?},\ndata:{:?},\ngas_limit:{:?}", &from, &to, &data, &gas_limit);
-            let result = Ethink::contract_call(from.into(), to.into(), data, value, gas_limit)
-                .result?;
-            if result.did_revert() {
-                log::error!("Contract {:?} call reverted: {:?}", &to, &result.data);
-                return Err(DispatchError::Other("Contract call reverted"))
-            }
-            // NOTE: ink! returns returned val wrapped into Result, which takes 1st byte
-            // here we rm it to get the inner value only.
-            // Proper solution would be to upstream abi logic into ink!.
-            let dbg = result.data[1..].to_vec();
-            log::debug!("Contract returned val: {:x?}", &dbg);
-            Ok(dbg)
+            value: U256,
+            gas_limit: U256,
+        ) -> Result<Vec<u8>, pallet_ethink::DryRunError> {
+            eth_call(from, to, data, value, gas_limit)
         }
 
-        fn gas_estimate(
+        fn call_with_overrides(
             from: H160,
             to: H160,
             data: Vec<u8>,
-            value: u128,
-            gas_limit: Weight,
-        ) -> Result<U256, DispatchError> {
+            value: U256,
+            gas_limit: U256,
+            overrides: Vec<(H160, pallet_ethink::StateOverride)>,
+        ) -> Result<Vec<u8>, pallet_ethink::DryRunError> {
+            // Overrides are applied and rolled back regardless of the call outcome
+            Ethink::with_overrides(overrides, || Ok(eth_call(from, to, data, value, gas_limit)))?
+        }
+
+        fn gas_estimate(
+            from: H160,
+            to: Option<H160>,
+            data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+        ) -> Result<U256, pallet_ethink::DryRunError> {
             log::debug!("Estimating Gas for call from: {from:?}, to: {to:?}, data: {} GAS_LIMIT: {:?}", hex::encode(&data), &gas_limit);
-            let dbg = Ethink::gas_estimate(from.into(), to.into(), data, value, gas_limit);
+            let value = Ethink::wei_to_native(value).map_err(DispatchError::from)?;
+            let dbg = Ethink::gas_estimate(from.into(), to.map(Into::into), data, value, gas_limit);
             log::debug!("Estimated Gas: {:?}", &dbg);
             dbg
         }
 
+        fn current_block() -> Option<pallet_ethink::EthBlock> {
+            pallet_ethink::CurrentBlock::<Runtime>::get()
+        }
+
+        fn current_receipts() -> Option<Vec<pallet_ethink::Receipt>> {
+            pallet_ethink::CurrentReceipts::<Runtime>::get()
+        }
+
+        fn current_transaction_statuses() -> Option<Vec<pallet_ethink::TransactionStatus>> {
+            pallet_ethink::CurrentTransactionStatuses::<Runtime>::get()
+        }
+
+        fn transaction_location(hash: H256) -> Option<pallet_ethink::TransactionLocation> {
+            Ethink::transaction_location(hash)
+        }
+
         fn build_extrinsic(
              from: EthTransaction,
          ) -> <Block as BlockT>::Extrinsic {
@@ -930,3 +1012,26 @@ impl_runtime_apis! {
 
 // Implement ethink! executor for Contracts
 pallet_ethink::impl_executor!(Runtime, Contracts);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_support::traits::Get;
+    use pallet_ethink::DepositLimit;
+
+    #[test]
+    fn eth_deposit_limit_is_bounded_and_allows_deploying_largest_code() {
+        type Limit = <Runtime as pallet_ethink::Config>::DepositLimit;
+        type MaxCodeLen = <Runtime as pallet_contracts::Config>::MaxCodeLen;
+        // Bounded regardless of the fee budget
+        assert_eq!(Limit::deposit_limit(0), Some(DefaultDepositLimit::get()));
+        assert_eq!(
+            Limit::deposit_limit(Balance::MAX),
+            Some(DefaultDepositLimit::get())
+        );
+        // Yet enough to upload code of maximum size along with a new contract
+        let code_deposit = DepositPerByte::get() * MaxCodeLen::get() as Balance;
+        let contract_deposit = DepositPerItem::get() + ED;
+        assert!(Limit::deposit_limit(0).unwrap() > code_deposit + contract_deposit);
+    }
+}